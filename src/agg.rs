@@ -0,0 +1,114 @@
+use redis_module::{Context, NextArg, RedisError, RedisResult, RedisValue};
+
+use fulltree::{Node, Traversal};
+
+use crate::value::Value;
+use crate::{next_depth, parse_path, Record, RedisTree, TREE_TYPE};
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregate {
+    Sum,
+    Min,
+    Max,
+    Avg,
+    Count,
+}
+
+impl Aggregate {
+    pub fn parse(name: &str) -> Option<Aggregate> {
+        match name.to_ascii_uppercase().as_str() {
+            "SUM" => Some(Aggregate::Sum),
+            "MIN" => Some(Aggregate::Min),
+            "MAX" => Some(Aggregate::Max),
+            "AVG" => Some(Aggregate::Avg),
+            "COUNT" => Some(Aggregate::Count),
+            _ => None,
+        }
+    }
+
+    /// Aggregates the numeric values of `field` in `node` and its descendants down to `depth` levels below it.
+    /// Nodes without the field or with a non numeric value are skipped.
+    /// Sums of ints stay ints unless they overflow. Returns `None` for the MIN, MAX and AVG of no values.
+    pub fn run(self, node: &Node<Record>, field: &str, depth: Option<usize>) -> Option<Value> {
+        let mut count = 0i64;
+        let mut int_sum = Some(0i64);
+        let mut sum = 0.0;
+        let mut best: Option<&Value> = None;
+
+        for value in values(node, field, depth) {
+            count += 1;
+            sum += value.as_f64().expect("values are numeric");
+            int_sum = match (int_sum, value) {
+                (Some(total), Value::Int(int)) => total.checked_add(*int),
+                _ => None,
+            };
+            best = match best {
+                Some(best) if !self.prefers(value, best) => Some(best),
+                _ => Some(value),
+            };
+        }
+
+        match self {
+            Aggregate::Sum => Some(int_sum.map_or(Value::Float(sum), Value::Int)),
+            Aggregate::Count => Some(Value::Int(count)),
+            Aggregate::Avg if count > 0 => Some(Value::Float(sum / count as f64)),
+            Aggregate::Min | Aggregate::Max => best.cloned(),
+            Aggregate::Avg => None,
+        }
+    }
+
+    /// The result over no values.
+    pub fn empty(self) -> Option<Value> {
+        match self {
+            Aggregate::Sum | Aggregate::Count => Some(Value::Int(0)),
+            _ => None,
+        }
+    }
+
+    fn prefers(self, value: &Value, best: &Value) -> bool {
+        match (self, value.compare(best)) {
+            (Aggregate::Min, Some(ordering)) => ordering.is_lt(),
+            (Aggregate::Max, Some(ordering)) => ordering.is_gt(),
+            _ => false,
+        }
+    }
+}
+
+/// Numeric values of `field` in pre-order, the walk stops `depth` levels below `node`.
+fn values<'a>(node: &'a Node<Record>, field: &'a str, depth: Option<usize>) -> impl Iterator<Item = &'a Value> {
+    let traversal = match depth {
+        Some(depth) => Traversal::new().max_depth(depth),
+        None => Traversal::new(),
+    };
+    node.dfs_depths().limit(traversal)
+        .filter_map(move |(_, node)| node.data.fields.get(field))
+        .filter(|value| value.as_f64().is_some())
+}
+
+/// TREE.AGG key path field SUM|MIN|MAX|AVG|COUNT [DEPTH n]
+///
+/// Aggregates a numeric field over the node at `path` and its descendants,
+/// `DEPTH 0` being the node alone. Floats are replied as bulk strings, missing results as nil.
+pub fn tree_agg(ctx: &Context, args: Vec<String>) -> RedisResult {
+    let mut args = args.into_iter().skip(1);
+    let key = args.next_string()?;
+    let path = parse_path(&args.next_string()?)?;
+    let field = args.next_string()?;
+    let aggregate = Aggregate::parse(&args.next_string()?).ok_or(RedisError::Str("ERR syntax error"))?;
+    let depth = next_depth(&mut args)?;
+    args.done()?;
+
+    let key = ctx.open_key(&key);
+    let node = key.get_value::<RedisTree>(&TREE_TYPE)?.and_then(|value| value.get(&path));
+    let result = match node {
+        Some(node) => aggregate.run(node, &field, depth),
+        None => aggregate.empty(),
+    };
+
+    Ok(match result {
+        Some(Value::Int(int)) => RedisValue::Integer(int),
+        Some(value) => RedisValue::BulkString(value.to_string()),
+        None => RedisValue::Null,
+    })
+}
@@ -0,0 +1,67 @@
+use std::os::raw::{c_char, c_void};
+use std::ptr;
+
+use redis_module::raw;
+
+use crate::RedisTree;
+
+
+/// Rough count of arguments per emitted `TREE.MSET`, a single record is never split.
+const AOF_BATCH_ARGS: usize = 512;
+
+
+/// Rewrites a key as `TREE.MSET key path count field value ... [path ...]` commands,
+/// followed by a `TREE.ROLLUP key ADD field SUM` per rollup field.
+///
+/// Nodes are visited in pre-order, so replaying the paths creates children in their
+/// original order. Intermediate nodes without fields are left out: inserting their first
+/// descendant creates them at the same position. The root is always emitted, so that
+/// a key holding nothing but an empty root is recreated, and its rollups with it.
+pub unsafe extern "C" fn aof_rewrite(aof: *mut raw::RedisModuleIO, key: *mut raw::RedisModuleString, value: *mut c_void) {
+    let value = &*(value as *mut RedisTree);
+    let mut batch: Vec<String> = Vec::new();
+
+    for (path, node) in value.tree.root().dfs_paths() {
+        let record = &node.data;
+        if path.is_empty() || !record.fields.is_empty() || node.degree() == 0 {
+            batch.push(path.to_string());
+            batch.push(record.fields.len().to_string());
+            for (field, value) in &record.fields {
+                batch.push(field.clone());
+                batch.push(value.to_string());
+            }
+            if batch.len() >= AOF_BATCH_ARGS {
+                emit(aof, b"TREE.MSET\0", key, &batch);
+                batch.clear();
+            }
+        }
+    }
+
+    if !batch.is_empty() {
+        emit(aof, b"TREE.MSET\0", key, &batch);
+    }
+    for field in &value.rollups {
+        emit(aof, b"TREE.ROLLUP\0", key, &["ADD".to_owned(), field.clone(), "SUM".to_owned()]);
+    }
+}
+
+/// Emits `command key args...`, `command` being nul terminated.
+unsafe fn emit(aof: *mut raw::RedisModuleIO, command: &[u8], key: *mut raw::RedisModuleString, args: &[String]) {
+    let create_string = raw::RedisModule_CreateString.unwrap();
+    let free_string = raw::RedisModule_FreeString.unwrap();
+
+    let mut argv: Vec<*mut raw::RedisModuleString> = args.iter()
+        .map(|arg| create_string(ptr::null_mut(), arg.as_ptr() as *const c_char, arg.len()))
+        .collect();
+    raw::RedisModule_EmitAOF.unwrap()(
+        aof,
+        command.as_ptr() as *const c_char,
+        b"sv\0".as_ptr() as *const c_char,
+        key,
+        argv.as_mut_ptr(),
+        argv.len(),
+    );
+    for arg in argv {
+        free_string(ptr::null_mut(), arg);
+    }
+}
@@ -0,0 +1,43 @@
+use std::hash::{Hash, Hasher};
+use std::os::raw::c_void;
+
+use redis_module::raw;
+
+use crate::RedisTree;
+
+
+/// Feeds a tree into `DEBUG DIGEST` through the structural `Hash` impl of `Tree`:
+/// labels, fields and child counts in pre-order, so equal trees give equal digests.
+/// The rollup fields follow, their totals only depend on the tree.
+pub unsafe extern "C" fn digest(md: *mut raw::RedisModuleDigest, value: *mut c_void) {
+    let value = &*(value as *mut RedisTree);
+    let mut hasher = DigestHasher { md };
+    value.tree.hash(&mut hasher);
+    value.rollups.hash(&mut hasher);
+    raw::RedisModule_DigestEndSequence.unwrap()(md);
+}
+
+
+/// Forwards the bytes written by `Hash` impls to the digest, in order.
+struct DigestHasher {
+    md: *mut raw::RedisModuleDigest,
+}
+
+impl Hasher for DigestHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        unsafe {
+            raw::RedisModule_DigestAddStringBuffer.unwrap()(self.md, bytes.as_ptr() as *mut u8, bytes.len());
+        }
+    }
+
+    // Lengths and counts are added as numbers to keep the digest independent of the pointer width.
+    fn write_usize(&mut self, n: usize) {
+        unsafe {
+            raw::RedisModule_DigestAddLongLong.unwrap()(self.md, n as i64);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        0
+    }
+}
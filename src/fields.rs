@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::mem;
+use std::slice;
+
+use crate::value::Value;
+
+
+/// Number of fields from which a node indexes its fields by name.
+const INDEX_THRESHOLD: usize = 16;
+
+
+/// Field/value pairs of a node, kept in insertion order like a redis hash listing.
+///
+/// Nodes rarely carry more than a handful of fields, so lookups scan the pairs
+/// until there are `INDEX_THRESHOLD` of them, then go through an index of their positions.
+#[derive(Debug, Clone, Default)]
+pub struct Fields {
+    pairs: Vec<(String, Value)>,
+    index: Option<HashMap<String, usize>>,
+}
+
+impl Fields {
+    pub fn new() -> Self {
+        Fields { pairs: Vec::new(), index: None }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Fields { pairs: Vec::with_capacity(capacity), index: None }
+    }
+
+    pub fn len(&self) -> usize { self.pairs.len() }
+    pub fn is_empty(&self) -> bool { self.pairs.is_empty() }
+
+    fn position(&self, field: &str) -> Option<usize> {
+        match &self.index {
+            Some(index) => index.get(field).copied(),
+            None => self.pairs.iter().position(|(name, _)| name == field),
+        }
+    }
+
+    pub fn get(&self, field: &str) -> Option<&Value> {
+        self.position(field).map(|position| &self.pairs[position].1)
+    }
+
+    /// Sets `field`, keeping its position if it exists. Returns whether the field is new.
+    pub fn insert(&mut self, field: String, value: Value) -> bool {
+        match self.position(&field) {
+            Some(position) => {
+                self.pairs[position].1 = value;
+                false
+            }
+            None => {
+                match &mut self.index {
+                    Some(index) => { index.insert(field.clone(), self.pairs.len()); }
+                    None if self.pairs.len() + 1 >= INDEX_THRESHOLD => {
+                        let index = self.pairs.iter().enumerate().map(|(position, (name, _))| (name.clone(), position));
+                        self.index = Some(index.chain(Some((field.clone(), self.pairs.len()))).collect());
+                    }
+                    None => (),
+                }
+                self.pairs.push((field, value));
+                true
+            }
+        }
+    }
+
+    /// Removes `field`, the remaining fields keep their order. Returns whether it existed.
+    pub fn remove(&mut self, field: &str) -> bool {
+        match self.position(field) {
+            Some(position) => {
+                self.pairs.remove(position);
+                if let Some(index) = &mut self.index {
+                    index.remove(field);
+                    for (name, _) in &self.pairs[position..] {
+                        *index.get_mut(name).expect("indexed field") -= 1;
+                    }
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn iter(&self) -> slice::Iter<'_, (String, Value)> {
+        self.pairs.iter()
+    }
+
+    /// Bytes allocated on the heap by the pairs and their strings, and by the index.
+    pub fn heap_size(&self) -> usize {
+        self.pairs.capacity() * mem::size_of::<(String, Value)>()
+            + self.pairs.iter().map(|(field, value)| field.capacity() + value.heap_size()).sum::<usize>()
+            + self.index.as_ref().map_or(0, |index| {
+                index.capacity() * mem::size_of::<(String, usize)>() + index.keys().map(String::capacity).sum::<usize>()
+            })
+    }
+}
+
+// The index only speeds lookups up, fields are compared and hashed by their pairs.
+impl PartialEq for Fields {
+    fn eq(&self, other: &Fields) -> bool { self.pairs == other.pairs }
+}
+
+impl Eq for Fields {}
+
+impl Hash for Fields {
+    fn hash<H: Hasher>(&self, state: &mut H) { self.pairs.hash(state) }
+}
+
+impl<'a> IntoIterator for &'a Fields {
+    type Item = &'a (String, Value);
+    type IntoIter = slice::Iter<'a, (String, Value)>;
+    fn into_iter(self) -> Self::IntoIter { self.pairs.iter() }
+}
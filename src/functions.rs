@@ -0,0 +1,80 @@
+use redis_module::{Context, NextArg, RedisError, RedisResult, RedisValue};
+
+use fulltree::{LtreePath, PathError};
+
+use crate::parse_path;
+
+
+// The Postgres ltree functions, over paths given as arguments rather than stored in a key.
+
+fn reply_path(path: Result<LtreePath, PathError>) -> RedisResult {
+    match path {
+        Ok(path) => Ok(RedisValue::BulkString(path.to_string())),
+        Err(e) => Err(RedisError::String(format!("ERR {}", e))),
+    }
+}
+
+/// TREE.NLEVEL path
+pub fn tree_nlevel(_ctx: &Context, args: Vec<String>) -> RedisResult {
+    let mut args = args.into_iter().skip(1);
+    let path = parse_path(&args.next_string()?)?;
+    args.done()?;
+
+    Ok((path.nlevel() as i64).into())
+}
+
+/// TREE.SUBLTREE path start end
+pub fn tree_subltree(_ctx: &Context, args: Vec<String>) -> RedisResult {
+    let mut args = args.into_iter().skip(1);
+    let path = parse_path(&args.next_string()?)?;
+    let start = args.next_i64()?;
+    let end = args.next_i64()?;
+    args.done()?;
+
+    reply_path(path.subltree(start as isize, end as isize))
+}
+
+/// TREE.SUBPATH path offset [len]
+pub fn tree_subpath(_ctx: &Context, args: Vec<String>) -> RedisResult {
+    let mut args = args.into_iter().skip(1);
+    let path = parse_path(&args.next_string()?)?;
+    let offset = args.next_i64()?;
+    let len = match args.next() {
+        Some(len) => Some(len.parse::<i64>()?),
+        None => None,
+    };
+    args.done()?;
+
+    reply_path(path.subpath(offset as isize, len.map(|len| len as isize)))
+}
+
+/// TREE.INDEX path subpath [offset]
+///
+/// Replies with the position of `subpath` in `path`, -1 if not found.
+pub fn tree_index(_ctx: &Context, args: Vec<String>) -> RedisResult {
+    let mut args = args.into_iter().skip(1);
+    let path = parse_path(&args.next_string()?)?;
+    let sub = parse_path(&args.next_string()?)?;
+    let offset = match args.next() {
+        Some(offset) => offset.parse::<i64>()?,
+        None => 0,
+    };
+    args.done()?;
+
+    Ok(path.index(&sub, offset as isize).map_or(-1, |i| i as i64).into())
+}
+
+/// TREE.LCA path [path ...]
+///
+/// Replies with the longest common ancestor, nil if one of the paths is empty.
+pub fn tree_lca(_ctx: &Context, args: Vec<String>) -> RedisResult {
+    if args.len() < 2 {
+        return Err(RedisError::WrongArity);
+    }
+    let paths = args[1..].iter().map(|path| parse_path(path)).collect::<Result<Vec<_>, _>>()?;
+
+    Ok(match LtreePath::lca(&paths) {
+        Some(lca) => RedisValue::BulkString(lca.to_string()),
+        None => RedisValue::Null,
+    })
+}
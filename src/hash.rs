@@ -0,0 +1,207 @@
+use fulltree::LtreePath;
+use redis_module::{Context, NextArg, RedisError, RedisResult, RedisValue};
+
+use crate::fields::Fields;
+use crate::value::Value;
+use crate::{parse_path, replicate, reply_fields, RedisTree, TREE_TYPE};
+
+
+/// Runs `f` on the fields at `path`, creating the key and the path if missing.
+fn update_fields<R, F: FnOnce(&mut Fields) -> R>(ctx: &Context, key: &str, path: &LtreePath, f: F) -> Result<R, RedisError> {
+    let key = ctx.open_key_writable(key);
+    match key.get_value::<RedisTree>(&TREE_TYPE)? {
+        Some(value) => Ok(value.update_fields(path, true, f).expect("path was created")),
+        None => {
+            let mut value = RedisTree::new();
+            let result = value.update_fields(path, true, f).expect("path was created");
+            key.set_value(&TREE_TYPE, value)?;
+            Ok(result)
+        }
+    }
+}
+
+/// Stores the value `f` computes from the current value of `field` at `path`, if any,
+/// creating the key and the path if missing. Nothing is created when `f` fails.
+fn increment_field<F: FnOnce(Option<&Value>) -> Result<Value, RedisError>>(ctx: &Context, key: &str, path: &LtreePath, field: String, f: F) -> Result<Value, RedisError> {
+    let value = {
+        let key = ctx.open_key(key);
+        let current = key.get_value::<RedisTree>(&TREE_TYPE)?
+            .and_then(|value| value.get(path))
+            .and_then(|node| node.data.fields.get(&field));
+        f(current)?
+    };
+    update_fields(ctx, key, path, |fields| fields.insert(field, value.clone()))?;
+    Ok(value)
+}
+
+/// TREE.HSET key path field value [field value ...]
+///
+/// Sets the given fields, keeping the others. Replies with the number of fields added.
+pub fn tree_hset(ctx: &Context, args: Vec<String>) -> RedisResult {
+    if args.len() < 5 || args.len() % 2 == 0 {
+        return Err(RedisError::WrongArity);
+    }
+
+    let mut args = args.into_iter().skip(1);
+    let key = args.next_string()?;
+    let path = parse_path(&args.next_string()?)?;
+    let mut pairs = Vec::new();
+    while let Some(field) = args.next() {
+        pairs.push((field, Value::parse(args.next_string()?)));
+    }
+
+    let added = update_fields(ctx, &key, &path, |fields| {
+        pairs.into_iter().map(|(field, value)| fields.insert(field, value)).filter(|&added| added).count()
+    })?;
+    replicate(ctx, "tree.hset", &key);
+
+    Ok((added as i64).into())
+}
+
+/// TREE.HGET key path field
+pub fn tree_hget(ctx: &Context, args: Vec<String>) -> RedisResult {
+    let mut args = args.into_iter().skip(1);
+    let key = args.next_string()?;
+    let path = parse_path(&args.next_string()?)?;
+    let field = args.next_string()?;
+    args.done()?;
+
+    let key = ctx.open_key(&key);
+    let value = key.get_value::<RedisTree>(&TREE_TYPE)?
+        .and_then(|value| value.get(&path))
+        .and_then(|node| node.data.fields.get(&field));
+
+    Ok(match value {
+        Some(value) => RedisValue::BulkString(value.to_string()),
+        None => RedisValue::Null,
+    })
+}
+
+/// TREE.HDEL key path field [field ...]
+///
+/// Replies with the number of fields removed, the node itself is kept
+/// unless it is the root of a key left with nothing, which is deleted.
+pub fn tree_hdel(ctx: &Context, args: Vec<String>) -> RedisResult {
+    if args.len() < 4 {
+        return Err(RedisError::WrongArity);
+    }
+
+    let mut args = args.into_iter().skip(1);
+    let key = args.next_string()?;
+    let path = parse_path(&args.next_string()?)?;
+    let names: Vec<String> = args.collect();
+
+    let redis_key = ctx.open_key_writable(&key);
+    let removed = match redis_key.get_value::<RedisTree>(&TREE_TYPE)? {
+        Some(value) => {
+            let removed = value
+                .update_fields(&path, false, |fields| names.iter().filter(|name| fields.remove(name)).count())
+                .unwrap_or(0);
+            // As with redis hashes, removing the last field of a key deletes the key.
+            if value.is_empty() {
+                redis_key.delete()?;
+            }
+            removed
+        }
+        None => 0,
+    };
+    if removed > 0 {
+        replicate(ctx, "tree.hdel", &key);
+    }
+
+    Ok((removed as i64).into())
+}
+
+/// TREE.HGETALL key path
+///
+/// Replies with an empty array if the path does not exist.
+pub fn tree_hgetall(ctx: &Context, args: Vec<String>) -> RedisResult {
+    let mut args = args.into_iter().skip(1);
+    let key = args.next_string()?;
+    let path = parse_path(&args.next_string()?)?;
+    args.done()?;
+
+    let key = ctx.open_key(&key);
+    let node = key.get_value::<RedisTree>(&TREE_TYPE)?.and_then(|value| value.get(&path));
+
+    Ok(match node {
+        Some(node) => reply_fields(&node.data),
+        None => RedisValue::Array(Vec::new()),
+    })
+}
+
+/// TREE.HINCRBY key path field increment
+///
+/// A missing field counts as 0. Replies with the new value.
+pub fn tree_hincrby(ctx: &Context, args: Vec<String>) -> RedisResult {
+    let mut args = args.into_iter().skip(1);
+    let key = args.next_string()?;
+    let path = parse_path(&args.next_string()?)?;
+    let field = args.next_string()?;
+    let increment = args.next_i64()?;
+    args.done()?;
+
+    let value = increment_field(ctx, &key, &path, field, |current| {
+        let current = match current {
+            Some(Value::Int(int)) => *int,
+            Some(_) => return Err(RedisError::Str("ERR hash value is not an integer")),
+            None => 0,
+        };
+        let value = current.checked_add(increment).ok_or(RedisError::Str("ERR increment or decrement would overflow"))?;
+        Ok(Value::Int(value))
+    })?;
+    replicate(ctx, "tree.hincrby", &key);
+
+    match value {
+        Value::Int(int) => Ok(int.into()),
+        _ => unreachable!("TREE.HINCRBY stores ints"),
+    }
+}
+
+/// TREE.HINCRBYFLOAT key path field increment
+///
+/// Works on ints and floats, a missing field counts as 0. The result is a float.
+pub fn tree_hincrbyfloat(ctx: &Context, args: Vec<String>) -> RedisResult {
+    let mut args = args.into_iter().skip(1);
+    let key = args.next_string()?;
+    let path = parse_path(&args.next_string()?)?;
+    let field = args.next_string()?;
+    let increment = args.next_f64()?;
+    args.done()?;
+
+    let value = increment_field(ctx, &key, &path, field, |current| {
+        let current = match current {
+            Some(value) => value.as_f64().ok_or(RedisError::Str("ERR hash value is not a float"))?,
+            None => 0.0,
+        };
+        let value = current + increment;
+        if !value.is_finite() {
+            return Err(RedisError::Str("ERR increment would produce NaN or Infinity"));
+        }
+        Ok(Value::Float(value))
+    })?;
+    replicate(ctx, "tree.hincrbyfloat", &key);
+
+    Ok(RedisValue::BulkString(value.to_string()))
+}
+
+/// TREE.HTYPE key path field
+///
+/// Replies with the type of the field: int, float, string, bool or null; nil if there is no such field.
+pub fn tree_htype(ctx: &Context, args: Vec<String>) -> RedisResult {
+    let mut args = args.into_iter().skip(1);
+    let key = args.next_string()?;
+    let path = parse_path(&args.next_string()?)?;
+    let field = args.next_string()?;
+    args.done()?;
+
+    let key = ctx.open_key(&key);
+    let value = key.get_value::<RedisTree>(&TREE_TYPE)?
+        .and_then(|value| value.get(&path))
+        .and_then(|node| node.data.fields.get(&field));
+
+    Ok(match value {
+        Some(value) => RedisValue::SimpleStringStatic(value.type_name()),
+        None => RedisValue::Null,
+    })
+}
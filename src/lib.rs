@@ -1,58 +1,334 @@
-
 #[macro_use]
 extern crate redis_module;
 
 use redis_module::native_types::RedisType;
-use redis_module::{raw, Context, NextArg, RedisResult, REDIS_OK};
-use redis_module::logging::{log};
-use redis_module::LogLevel;
+use redis_module::{raw, Context, NextArg, NotifyEvent, RedisError, RedisResult, RedisValue, REDIS_OK};
+use std::hash::{Hash, Hasher};
+use std::mem;
 use std::os::raw::c_void;
-use std::rc::Rc;
-use std::ptr;
 
+use fulltree::{Label, LtreePath, Node, Tree};
 
-fn inner_log(message: &str) {
-    log(LogLevel::Warning, message)
-}
+mod query;
+use query::Query;
 
+mod rdb;
+mod aof;
+mod digest;
 
+mod value;
+use value::Value;
 
-#[derive(Debug)]
-struct Inner {
-    data: String,
+mod fields;
+use fields::Fields;
+
+mod hash;
+mod agg;
+mod rollup;
+use rollup::Total;
+mod functions;
+
+
+/// Payload carried by every node of a tree key: the node's own label
+/// plus the fields stored at that path.
+///
+/// `totals` holds the sums over the subtree of the rollup fields of the key,
+/// in the order of `RedisTree::rollups`. Missing trailing totals are 0.
+#[derive(Debug, Clone)]
+struct Record {
+    label: String,
+    fields: Fields,
+    totals: Vec<Total>,
 }
 
-impl Drop for Inner {
-    fn drop(&mut self) { 
-        inner_log("drop in inner")
+impl Label for Record {
+    fn label(&self) -> &str { &self.label }
+
+    fn from_label(label: &str) -> Self {
+        Record { label: label.to_owned(), fields: Fields::new(), totals: Vec::new() }
     }
 }
 
-impl Drop for MyType {
-    fn drop(&mut self) { 
-        inner_log("drop in myType")
+// Totals derive from the fields, they are left out.
+impl Hash for Record {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.label.hash(state);
+        self.fields.hash(state);
+    }
+}
+
+impl Record {
+    /// Bytes allocated on the heap by the label and fields, the record itself lives inline in its node.
+    fn heap_size(&self) -> usize {
+        self.label.capacity() + self.fields.heap_size()
     }
 }
 
 
+/// Value stored under a redis key. The root node is the empty path,
+/// its descendants are addressed by `.` separated label paths.
+///
+/// `heap` caches the bytes allocated by the labels and fields, so that together with
+/// the node count kept by the tree the memory usage is known in O(1).
+/// `rollups` are the fields whose totals every node keeps, see `TREE.ROLLUP`.
 #[derive(Debug)]
-struct MyType {
-    data: Box<Inner>,
+struct RedisTree {
+    tree: Tree<Record>,
+    heap: usize,
+    rollups: Vec<String>,
+}
+
+impl RedisTree {
+    fn new() -> Self {
+        RedisTree::from_tree(Tree::new(Record::from_label("")), Vec::new())
+    }
+
+    fn from_tree(mut tree: Tree<Record>, rollups: Vec<String>) -> Self {
+        let heap = heap_size(tree.root());
+        rollup::compute_totals(&rollups, tree.root_mut().get_mut());
+        tree.root_mut().get_mut().build_signatures();
+        tree.root_mut().get_mut().index_wide_nodes();
+        RedisTree { tree, heap, rollups }
+    }
+
+    /// Approximate bytes used by the key: the nodes with their links and inline records,
+    /// plus the labels, fields and totals. The child lookup indexes of wide nodes are not counted.
+    fn mem_usage(&self) -> usize {
+        mem::size_of::<Self>()
+            + self.tree.node_count() * (mem::size_of::<Node<Record>>() + self.rollups.len() * mem::size_of::<Total>())
+            + self.rollups.iter().map(String::capacity).sum::<usize>()
+            + self.heap
+    }
+
+    fn get(&self, path: &LtreePath) -> Option<&Node<Record>> {
+        self.tree.get_path(path)
+    }
+
+    /// Creates the node at `path` and its missing ancestors, without fields.
+    fn create_path(&mut self, path: &LtreePath) {
+        let node_count = self.tree.node_count();
+        self.tree.entry(path).or_create();
+        let created = self.tree.node_count() - node_count;
+        self.heap += path.labels()[path.len() - created..].iter().map(String::len).sum::<usize>();
+    }
+
+    /// Runs `f` on the fields at `path`, which is created first if `create` is set,
+    /// and keeps the cached heap size up to date. Returns `None` if there is no such node.
+    fn update_fields<R, F: FnOnce(&mut Fields) -> R>(&mut self, path: &LtreePath, create: bool, f: F) -> Option<R> {
+        if create {
+            self.create_path(path);
+        }
+        let record = &mut self.tree.get_path_mut(path)?.get_mut().data;
+        let old_size = record.heap_size();
+        let old_values = rollup::values(&self.rollups, &record.fields);
+        let result = f(&mut record.fields);
+        let new_size = record.heap_size();
+        let new_values = rollup::values(&self.rollups, &record.fields);
+
+        self.heap = self.heap + new_size - old_size;
+        self.add_totals(path, &difference(&new_values, &old_values));
+        Some(result)
+    }
+
+    /// Replaces the fields of the node at `path`, creating it and its missing ancestors.
+    fn set_fields(&mut self, path: &LtreePath, fields: Fields) {
+        self.update_fields(path, true, |old| *old = fields);
+    }
+
+    /// Puts `subtree` at the non empty `path`, relabeling its root with the last label of `path`.
+    /// An existing node at `path` is replaced, in the same position, only if `replace` is set.
+    /// Returns whether the subtree was grafted.
+    fn graft(&mut self, path: &LtreePath, mut subtree: Tree<Record>, replace: bool) -> bool {
+        let (label, parent) = match (path.last(), path.parent()) {
+            (Some(label), Some(parent)) => (label, parent),
+            _ => return false,
+        };
+        if self.tree.get_path(path).is_some() && !replace {
+            return false;
+        }
+        subtree.root_mut().get_mut().data.label = label.to_owned();
+        rollup::compute_totals(&self.rollups, subtree.root_mut().get_mut());
+        subtree.root_mut().get_mut().build_signatures();
+        subtree.root_mut().get_mut().index_wide_nodes();
+        self.heap += heap_size(subtree.root());
+        let mut deltas = subtree.root().data.totals.clone();
+
+        self.create_path(&parent);
+        let parent_node = self.tree.get_path_mut(&parent).expect("parent was created").get_mut();
+        match parent_node.child_subnode(label) {
+            Some(sub) => {
+                let replaced = sub.replace(subtree);
+                self.heap -= heap_size(replaced.root());
+                deltas = difference(&deltas, &replaced.root().data.totals);
+            }
+            None => parent_node.push_back(subtree),
+        }
+        parent_node.build_index_if_wide();
+        self.add_totals(&parent, &deltas);
+        true
+    }
+
+    /// Removes the node at `path` as `mode` says, returns how many nodes were removed.
+    /// The root can not be removed this way.
+    fn remove(&mut self, path: &LtreePath, mode: DelMode) -> Result<usize, RedisError> {
+        let (last, parent_path) = match (path.last(), path.parent()) {
+            (Some(last), Some(parent)) => (last, parent),
+            _ => return Ok(0),
+        };
+        let parent = match self.tree.get_path_mut(&parent_path) {
+            Some(parent) => parent.get_mut(),
+            None => return Ok(0),
+        };
+        let node = match parent.child(last) {
+            Some(node) => node,
+            None => return Ok(0),
+        };
+
+        match mode {
+            DelMode::Cascade => (),
+            DelMode::Orphan => if node.degree() > 0 {
+                return Err(RedisError::Str("ERR node has children"));
+            },
+            DelMode::Lift => if let Some(child) = node.iter().find(|child| {
+                child.data.label != last && parent.child(&child.data.label).is_some()
+            }) {
+                return Err(RedisError::String(format!("ERR label '{}' already exists in the parent", child.data.label)));
+            },
+        }
+
+        let sub = parent.child_subnode(last).expect("the child was found above");
+        let removed = match mode {
+            DelMode::Lift => sub.lift(),
+            _ => sub.depart(),
+        };
+        parent.build_index_if_wide();
+        // A lifted node keeps the totals of the children it no longer has.
+        let removed_totals = match mode {
+            DelMode::Cascade => removed.root().data.totals.clone(),
+            _ => rollup::values(&self.rollups, &removed.root().data.fields),
+        };
+        self.heap -= heap_size(removed.root());
+        self.add_totals(&parent_path, &difference(&[], &removed_totals));
+        Ok(removed.node_count())
+    }
+
+    /// Moves the node at `src` with its subtree under `dst`, as the last child
+    /// or next to one of the children of `dst`. Nothing changes if an error is returned.
+    fn move_node(&mut self, src: &LtreePath, dst: &LtreePath, sibling: Option<(Place, &str)>) -> Result<(), RedisError> {
+        let (label, parent) = match (src.last(), src.parent()) {
+            (Some(label), Some(parent)) => (label.to_owned(), parent),
+            _ => return Err(RedisError::Str("ERR the root can not be moved")),
+        };
+        if src.is_ancestor_of(dst) {
+            return Err(RedisError::Str("ERR can not move a node into its own subtree"));
+        }
+        if self.tree.get_path(src).is_none() {
+            return Err(RedisError::Str("ERR no such path"));
+        }
+        let target = self.tree.get_path(dst).ok_or(RedisError::Str("ERR no such destination path"))?;
+        if *dst != parent && target.child(&label).is_some() {
+            return Err(RedisError::String(format!("ERR label '{}' already exists in the destination", label)));
+        }
+        if let Some((_, sibling)) = sibling {
+            if sibling == label || target.child(sibling).is_none() {
+                return Err(RedisError::Str("ERR no such sibling"));
+            }
+        }
+
+        let node = self.tree.get_path_mut(&parent).expect("src exists").get_mut()
+            .child_subnode(&label)
+            .expect("src exists")
+            .depart();
+        let totals = node.root().data.totals.clone();
+        let target = self.tree.get_path_mut(dst).expect("dst is not below src").get_mut();
+        match sibling {
+            Some((place, sibling)) => {
+                let mut sub = target.child_subnode(sibling).expect("sibling exists");
+                match place {
+                    Place::Before => sub.insert_before(node),
+                    Place::After => sub.insert_after(node),
+                }
+            }
+            None => target.push_back(node),
+        }
+        target.build_index_if_wide();
+        self.add_totals(&parent, &difference(&[], &totals));
+        self.add_totals(dst, &totals);
+        Ok(())
+    }
+
+    /// Whether the key holds nothing: no nodes below the root and no fields on it.
+    fn is_empty(&self) -> bool {
+        self.tree.degree() == 0 && self.tree.root().data.fields.is_empty()
+    }
+}
+
+
+/// How `TREE.DEL` treats the children of the removed node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DelMode {
+    /// Remove the whole subtree.
+    Cascade,
+    /// Only remove leaves, fail if the node has children.
+    Orphan,
+    /// Move the children into the parent, in place of the node.
+    Lift,
+}
+
+/// Where `TREE.MOVE` puts the node relative to the given sibling.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Place {
+    Before,
+    After,
+}
+
+/// `lhs - rhs` item by item, missing items being 0.
+fn difference(lhs: &[Total], rhs: &[Total]) -> Vec<Total> {
+    (0..lhs.len().max(rhs.len()))
+        .map(|i| lhs.get(i).cloned().unwrap_or_default() - rhs.get(i).cloned().unwrap_or_default())
+        .collect()
+}
+
+/// Sum of `Record::heap_size` over `node` and its descendants.
+fn heap_size(node: &Node<Record>) -> usize {
+    node.bfs().iter.map(|visit| visit.data.heap_size()).sum()
 }
 
-static MY_REDIS_TYPE: RedisType = RedisType::new(
-    "mytype123",
-    0,
+fn reply_fields(record: &Record) -> RedisValue {
+    RedisValue::Array(
+        record.fields.iter()
+            .flat_map(|(field, value)| vec![
+                RedisValue::BulkString(field.clone()),
+                RedisValue::BulkString(value.to_string()),
+            ])
+            .collect()
+    )
+}
+
+/// Propagates the running command to replicas and the AOF as is,
+/// and fires the `event` keyspace notification for `key`.
+fn replicate(ctx: &Context, event: &str, key: &str) {
+    ctx.replicate_verbatim();
+    ctx.notify_keyspace_event(NotifyEvent::MODULE, event, key);
+}
+
+fn parse_path(path: &str) -> Result<LtreePath, RedisError> {
+    LtreePath::parse(path).map_err(|e| RedisError::String(format!("ERR {}", e)))
+}
+
+
+static TREE_TYPE: RedisType = RedisType::new(
+    "retree-lt",
+    rdb::ENCODING_VERSION,
     raw::RedisModuleTypeMethods {
         version: raw::REDISMODULE_TYPE_METHOD_VERSION as u64,
-        rdb_load: None,
-        rdb_save: None,
-        aof_rewrite: None,
+        rdb_load: Some(rdb::rdb_load),
+        rdb_save: Some(rdb::rdb_save),
+        aof_rewrite: Some(aof::aof_rewrite),
         free: Some(free),
 
-        // Currently unused by Redis
-        mem_usage: None,
-        digest: None,
+        mem_usage: Some(mem_usage),
+        digest: Some(digest::digest),
 
         // Aux data
         aux_load: None,
@@ -62,66 +338,286 @@ static MY_REDIS_TYPE: RedisType = RedisType::new(
 );
 
 unsafe extern "C" fn free(value: *mut c_void) {
-    Box::from_raw(value as *mut MyType);
+    drop(Box::from_raw(value as *mut RedisTree));
 }
 
-fn alloc_set(ctx: &Context, args: Vec<String>) -> RedisResult {
-    let mut args = args.into_iter().skip(1);
-    let key = args.next_string()?;
-    let size = args.next_i64()?;
+unsafe extern "C" fn mem_usage(value: *const c_void) -> usize {
+    (*(value as *const RedisTree)).mem_usage()
+}
 
-    ctx.log_debug(format!("key: {}, size: {}", key, size).as_str());
 
-    let key = ctx.open_key_writable(&key);
+/// Reads `count` field/value pairs, a field repeated later overrides the earlier value.
+/// Never reserves room for more pairs than there are arguments left.
+fn next_fields<I: Iterator<Item = String>>(args: &mut I, count: usize) -> Result<Fields, RedisError> {
+    let mut fields = Fields::with_capacity(count.min(args.size_hint().0 / 2));
+    for _ in 0..count {
+        let field = args.next_string()?;
+        let value = args.next_string()?;
+        fields.insert(field, Value::parse(value));
+    }
+    Ok(fields)
+}
 
-    inner_log("hello set");
-    match key.get_value::<MyType>(&MY_REDIS_TYPE)? {
+/// Reads an optional trailing `DEPTH n`, how many levels below a node a command looks.
+fn next_depth<I: Iterator<Item = String>>(args: &mut I) -> Result<Option<usize>, RedisError> {
+    match args.next() {
+        Some(option) if option.eq_ignore_ascii_case("DEPTH") => {
+            let depth = args.next_i64()?;
+            if depth < 0 {
+                return Err(RedisError::Str("ERR depth must not be negative"));
+            }
+            Ok(Some(depth as usize))
+        }
+        Some(_) => Err(RedisError::Str("ERR syntax error")),
+        None => Ok(None),
+    }
+}
+
+/// Replaces the fields of every node at the given paths, creating missing nodes.
+fn set_records(ctx: &Context, key: &str, records: Vec<(LtreePath, Fields)>) -> Result<(), RedisError> {
+    let key = ctx.open_key_writable(key);
+    match key.get_value::<RedisTree>(&TREE_TYPE)? {
         Some(value) => {
+            for (path, fields) in records {
+                value.set_fields(&path, fields);
+            }
         }
         None => {
-            let value = MyType {
-                data: Box::new(Inner{
-                   data: "ABC".to_owned(), 
-                })
-            };
+            let mut value = RedisTree::new();
+            for (path, fields) in records {
+                value.set_fields(&path, fields);
+            }
+            key.set_value(&TREE_TYPE, value)?;
+        }
+    }
+    Ok(())
+}
+
+/// TREE.SET key path field value [field value ...]
+fn tree_set(ctx: &Context, args: Vec<String>) -> RedisResult {
+    if args.len() < 5 || args.len() % 2 == 0 {
+        return Err(RedisError::WrongArity);
+    }
 
-            inner_log(&format!("{:p}", value.data));
+    let count = (args.len() - 3) / 2;
+    let mut args = args.into_iter().skip(1);
+    let key = args.next_string()?;
+    let path = parse_path(&args.next_string()?)?;
+    let fields = next_fields(&mut args, count)?;
+
+    set_records(ctx, &key, vec![(path, fields)])?;
+    replicate(ctx, "tree.set", &key);
+    REDIS_OK
+}
 
-            key.set_value(&MY_REDIS_TYPE, value)?;
+/// TREE.MSET key path count field value [field value ...] [path count field value ...]
+///
+/// Sets several paths at once, `count` being the number of field/value pairs that follow.
+/// Nothing is written unless every path and count is valid.
+fn tree_mset(ctx: &Context, args: Vec<String>) -> RedisResult {
+    if args.len() < 4 {
+        return Err(RedisError::WrongArity);
+    }
+
+    let mut args = args.into_iter().skip(1);
+    let key = args.next_string()?;
+    let mut records = Vec::new();
+    while let Some(path) = args.next() {
+        let path = parse_path(&path)?;
+        let count = args.next_i64()?;
+        if count < 0 {
+            return Err(RedisError::Str("ERR field count must not be negative"));
         }
+        if count as u64 > (args.len() / 2) as u64 {
+            return Err(RedisError::WrongArity);
+        }
+        records.push((path, next_fields(&mut args, count as usize)?));
     }
 
-    Ok(size.into())
+    set_records(ctx, &key, records)?;
+    replicate(ctx, "tree.mset", &key);
+    REDIS_OK
 }
 
-fn alloc_del(ctx: &Context, args: Vec<String>) -> RedisResult {
+/// TREE.GET key path
+fn tree_get(ctx: &Context, args: Vec<String>) -> RedisResult {
     let mut args = args.into_iter().skip(1);
     let key = args.next_string()?;
+    let path = args.next_string()?;
+    args.done()?;
+    let path = parse_path(&path)?;
 
-    inner_log("hello del");
-    let key = ctx.open_key_writable(&key);
-    let value = match key.get_value::<MyType>(&MY_REDIS_TYPE)? {
-        Some(_) => {
-            key.delete()?;
-            "ok".into()
+    let key = ctx.open_key(&key);
+    let value = match key.get_value::<RedisTree>(&TREE_TYPE)? {
+        Some(value) => match value.get(&path) {
+            Some(node) => reply_fields(&node.data),
+            None => RedisValue::Null,
         },
-        None => "nil".into(),
-    };    
+        None => RedisValue::Null,
+    };
 
     Ok(value)
 }
 
+/// TREE.DEL key path [CASCADE|ORPHAN|LIFT]
+///
+/// Replies with the number of nodes removed, the whole subtree for CASCADE which is the default.
+/// Deleting the empty path deletes the key.
+fn tree_del(ctx: &Context, args: Vec<String>) -> RedisResult {
+    let mut args = args.into_iter().skip(1);
+    let key = args.next_string()?;
+    let path = args.next_string()?;
+    let mode = match args.next() {
+        Some(mode) => match mode.to_ascii_uppercase().as_str() {
+            "CASCADE" => DelMode::Cascade,
+            "ORPHAN" => DelMode::Orphan,
+            "LIFT" => DelMode::Lift,
+            _ => return Err(RedisError::Str("ERR syntax error")),
+        },
+        None => DelMode::Cascade,
+    };
+    args.done()?;
+    let path = parse_path(&path)?;
+
+    let redis_key = ctx.open_key_writable(&key);
+    let mut changed = false;
+    let removed = match redis_key.get_value::<RedisTree>(&TREE_TYPE)? {
+        Some(value) => {
+            let removed = if path.is_empty() {
+                match mode {
+                    DelMode::Orphan if value.tree.degree() > 0 => return Err(RedisError::Str("ERR node has children")),
+                    DelMode::Lift => return Err(RedisError::Str("ERR the root can not be lifted")),
+                    _ => value.tree.node_count() - 1,
+                }
+            } else {
+                value.remove(&path, mode)?
+            };
+            changed = removed > 0;
+            if path.is_empty() || value.is_empty() {
+                redis_key.delete()?;
+                changed = true;
+            }
+            removed
+        }
+        None => 0,
+    };
+    // Deleting a key holding only root fields removes no node but still changes the dataset.
+    if changed {
+        replicate(ctx, "tree.del", &key);
+    }
+
+    Ok((removed as i64).into())
+}
 
-fn alloc_get(ctx: &Context, args: Vec<String>) -> RedisResult {
+/// TREE.MOVE key src_path dst_path [BEFORE|AFTER sibling]
+///
+/// Moves the subtree at `src_path` under `dst_path`, as its last child unless a sibling is given.
+/// `src_path` keeps its last label, e.g. moving `Top.Science` to `Top.Archive` gives `Top.Archive.Science`.
+fn tree_move(ctx: &Context, args: Vec<String>) -> RedisResult {
     let mut args = args.into_iter().skip(1);
     let key = args.next_string()?;
-    let key = ctx.open_key(&key);
+    let src = parse_path(&args.next_string()?)?;
+    let dst = parse_path(&args.next_string()?)?;
+    let place = match args.next() {
+        Some(place) => match place.to_ascii_uppercase().as_str() {
+            "BEFORE" => Some((Place::Before, args.next_string()?)),
+            "AFTER" => Some((Place::After, args.next_string()?)),
+            _ => return Err(RedisError::Str("ERR syntax error")),
+        },
+        None => None,
+    };
+    args.done()?;
+
+    let redis_key = ctx.open_key_writable(&key);
+    match redis_key.get_value::<RedisTree>(&TREE_TYPE)? {
+        Some(value) => {
+            let sibling = place.as_ref().map(|(place, sibling)| (*place, sibling.as_str()));
+            value.move_node(&src, &dst, sibling)?;
+        }
+        None => return Err(RedisError::Str("ERR no such path")),
+    }
+    replicate(ctx, "tree.move", &key);
 
-    inner_log("hello get");
+    REDIS_OK
+}
 
-    let value = match key.get_value::<MyType>(&MY_REDIS_TYPE)? {
-        Some(value) => (&*value.data.data).into(),
-        None => ().into(),
+/// TREE.COPY srckey srcpath dstkey dstpath [REPLACE]
+///
+/// Copies the subtree at `srcpath` to `dstpath`, whose missing ancestors are created.
+/// Replies 1 when copied, 0 when `srcpath` does not exist or `dstpath` exists without REPLACE.
+/// An empty `dstpath` copies the subtree as the whole `dstkey`.
+fn tree_copy(ctx: &Context, args: Vec<String>) -> RedisResult {
+    let mut args = args.into_iter().skip(1);
+    let src_key = args.next_string()?;
+    let src = parse_path(&args.next_string()?)?;
+    let dst_key = args.next_string()?;
+    let dst = parse_path(&args.next_string()?)?;
+    let replace = match args.next() {
+        Some(replace) if replace.eq_ignore_ascii_case("REPLACE") => true,
+        Some(_) => return Err(RedisError::Str("ERR syntax error")),
+        None => false,
+    };
+    args.done()?;
+
+    let subtree = {
+        let key = ctx.open_key(&src_key);
+        match key.get_value::<RedisTree>(&TREE_TYPE)? {
+            Some(value) => match value.get(&src) {
+                Some(node) => node.to_owned(),
+                None => return Ok(0i64.into()),
+            },
+            None => return Ok(0i64.into()),
+        }
+    };
+
+    let key = ctx.open_key_writable(&dst_key);
+    let copied = if dst.is_empty() {
+        if !replace && key.get_value::<RedisTree>(&TREE_TYPE)?.is_some() {
+            false
+        } else {
+            let mut subtree = subtree;
+            subtree.root_mut().get_mut().data.label = String::new();
+            key.set_value(&TREE_TYPE, RedisTree::from_tree(subtree, Vec::new()))?;
+            true
+        }
+    } else {
+        match key.get_value::<RedisTree>(&TREE_TYPE)? {
+            Some(value) => value.graft(&dst, subtree, replace),
+            None => {
+                let mut value = RedisTree::new();
+                value.graft(&dst, subtree, replace);
+                key.set_value(&TREE_TYPE, value)?;
+                true
+            }
+        }
+    };
+    if copied {
+        replicate(ctx, "tree.copy", &dst_key);
+    }
+
+    Ok((copied as i64).into())
+}
+
+/// TREE.QUERY key "op operand" | TREE.QUERY key op operand [operand ...]
+///
+/// Replies with a `[path, [field, value, ...]]` pair for every matching node.
+fn tree_query(ctx: &Context, args: Vec<String>) -> RedisResult {
+    if args.len() < 3 {
+        return Err(RedisError::WrongArity);
+    }
+    let query = Query::parse(&args[2..])?;
+
+    let key = ctx.open_key(&args[1]);
+    let value = match key.get_value::<RedisTree>(&TREE_TYPE)? {
+        Some(value) => RedisValue::Array(
+            query.run(value.tree.root()).into_iter()
+                .map(|(path, record)| RedisValue::Array(vec![
+                    RedisValue::BulkString(path),
+                    reply_fields(record),
+                ]))
+                .collect()
+        ),
+        None => RedisValue::Array(Vec::new()),
     };
 
     Ok(value)
@@ -130,14 +626,38 @@ fn alloc_get(ctx: &Context, args: Vec<String>) -> RedisResult {
 //////////////////////////////////////////////////////
 
 redis_module! {
-    name: "alloc",
+    name: "retree",
     version: 1,
     data_types: [
-        MY_REDIS_TYPE,
+        TREE_TYPE,
     ],
     commands: [
-        ["alloc.set", alloc_set, "write", 1, 1, 1],
-        ["alloc.del", alloc_del, "write", 1, 1, 1],
-        ["alloc.get", alloc_get, "readonly", 1, 1, 1],
+        ["tree.set", tree_set, "write deny-oom", 1, 1, 1],
+        ["tree.mset", tree_mset, "write deny-oom", 1, 1, 1],
+        ["tree.get", tree_get, "readonly", 1, 1, 1],
+        ["tree.hset", hash::tree_hset, "write deny-oom", 1, 1, 1],
+        ["tree.hget", hash::tree_hget, "readonly", 1, 1, 1],
+        ["tree.hdel", hash::tree_hdel, "write", 1, 1, 1],
+        ["tree.hgetall", hash::tree_hgetall, "readonly", 1, 1, 1],
+        ["tree.hincrby", hash::tree_hincrby, "write deny-oom", 1, 1, 1],
+        ["tree.hincrbyfloat", hash::tree_hincrbyfloat, "write deny-oom", 1, 1, 1],
+        ["tree.htype", hash::tree_htype, "readonly", 1, 1, 1],
+        ["tree.del", tree_del, "write", 1, 1, 1],
+        ["tree.move", tree_move, "write", 1, 1, 1],
+        ["tree.copy", tree_copy, "write deny-oom", 1, 3, 2],
+        ["tree.agg", agg::tree_agg, "readonly", 1, 1, 1],
+        ["tree.rollup", rollup::tree_rollup, "write deny-oom", 1, 1, 1],
+        ["tree.total", rollup::tree_total, "readonly", 1, 1, 1],
+        ["tree.query", tree_query, "readonly", 1, 1, 1],
+        ["tree.firstancestor", query::tree_firstancestor, "readonly", 1, 1, 1],
+        ["tree.anymatch", query::tree_anymatch, "readonly", 1, 1, 1],
+        ["tree.firstdescendant", query::tree_firstdescendant, "readonly", 1, 1, 1],
+        ["tree.anytxtmatch", query::tree_anytxtmatch, "readonly", 1, 1, 1],
+        ["tree.children", query::tree_children, "readonly", 1, 1, 1],
+        ["tree.nlevel", functions::tree_nlevel, "readonly fast", 0, 0, 0],
+        ["tree.subltree", functions::tree_subltree, "readonly fast", 0, 0, 0],
+        ["tree.subpath", functions::tree_subpath, "readonly fast", 0, 0, 0],
+        ["tree.index", functions::tree_index, "readonly fast", 0, 0, 0],
+        ["tree.lca", functions::tree_lca, "readonly fast", 0, 0, 0],
     ],
-}
\ No newline at end of file
+}
@@ -0,0 +1,184 @@
+use fulltree::{Lquery, Ltxtquery, LtreePath, Node, Traversal};
+use redis_module::{Context, NextArg, RedisError, RedisResult, RedisValue};
+
+use crate::{next_depth, parse_path, Record, RedisTree, TREE_TYPE};
+
+
+/// A parsed `TREE.QUERY` operator with its operands, named after the ltree operators.
+pub enum Query {
+    /// `@> path`: nodes which are an ancestor of `path`, or `path` itself.
+    AncestorOf(LtreePath),
+    /// `<@ path`: nodes which are a descendant of `path`, or `path` itself.
+    DescendantOf(LtreePath),
+    /// `~ lquery`
+    Matches(Lquery),
+    /// `? lquery [lquery ...]`
+    MatchesAny(Vec<Lquery>),
+    /// `@ ltxtquery`
+    FullText(Ltxtquery),
+}
+
+impl Query {
+    /// Parses `op operand [operand ...]`. When the whole query is passed as a single
+    /// argument, e.g. `"<@ top"`, it is split on whitespace first.
+    /// The operands of `@` are joined back since an ltxtquery may contain spaces.
+    pub fn parse(args: &[String]) -> Result<Query, RedisError> {
+        let tokens: Vec<&str> = match args {
+            [query] => query.split_whitespace().collect(),
+            _ => args.iter().map(String::as_str).collect(),
+        };
+        let (op, operands) = match tokens.split_first() {
+            Some((op, operands)) if !operands.is_empty() => (*op, operands),
+            _ => return Err(RedisError::WrongArity),
+        };
+
+        let query = match op {
+            "@>" => Query::AncestorOf(parse_path(single(operands)?)?),
+            "<@" => Query::DescendantOf(parse_path(single(operands)?)?),
+            "~" => Query::Matches(parse_lquery(single(operands)?)?),
+            "?" => Query::MatchesAny(operands.iter().map(|q| parse_lquery(q)).collect::<Result<_, _>>()?),
+            "@" => Query::FullText(parse_ltxtquery(&operands.join(" "))?),
+            _ => return Err(RedisError::String(format!("ERR unknown tree query operator '{}'", op))),
+        };
+        Ok(query)
+    }
+
+    /// Records of the matching nodes below `root` with their paths.
+    /// `root` is the empty path and never part of the result.
+    pub fn run<'a>(&self, root: &'a Node<Record>) -> Vec<(String, &'a Record)> {
+        match self {
+            Query::AncestorOf(path) => {
+                let mut found = Vec::new();
+                let mut node = root;
+                for (depth, label) in path.iter().enumerate() {
+                    node = match node.child(label) {
+                        Some(child) => child,
+                        None => break,
+                    };
+                    found.push((path.labels()[..=depth].join("."), &node.data));
+                }
+                found
+            }
+            Query::DescendantOf(path) => match root.get_path(path) {
+                Some(node) => descendants(path, node),
+                None => Vec::new(),
+            },
+            Query::Matches(lquery) => into_strings(lquery.search(root)),
+            Query::MatchesAny(lqueries) => into_strings(Lquery::search_any(lqueries, root)),
+            Query::FullText(ltxtquery) => into_strings(ltxtquery.search(root)),
+        }
+    }
+}
+
+
+/// `node` and its descendants in breadth-first order, `path` being the path of `node`.
+fn descendants<'a>(path: &LtreePath, node: &'a Node<Record>) -> Vec<(String, &'a Record)> {
+    node.bfs_paths()
+        .map(|(relative, node)| {
+            let labels = path.iter().chain(relative.iter()).map(String::as_str).collect::<Vec<_>>();
+            (labels.join("."), &node.data)
+        })
+        .filter(|(path, _)| !path.is_empty())
+        .collect()
+}
+
+fn into_strings<'a>(found: Vec<(LtreePath, &'a Node<Record>)>) -> Vec<(String, &'a Record)> {
+    found.into_iter()
+        .filter(|(path, _)| !path.is_empty())
+        .map(|(path, node)| (path.to_string(), &node.data))
+        .collect()
+}
+
+fn single<'a>(operands: &[&'a str]) -> Result<&'a str, RedisError> {
+    match operands {
+        [operand] => Ok(operand),
+        _ => Err(RedisError::WrongArity),
+    }
+}
+
+fn parse_lquery(query: &str) -> Result<Lquery, RedisError> {
+    Lquery::parse(query).map_err(|e| RedisError::String(format!("ERR {}", e)))
+}
+
+fn parse_ltxtquery(query: &str) -> Result<Ltxtquery, RedisError> {
+    Ltxtquery::parse(query).map_err(|e| RedisError::String(format!("ERR {}", e)))
+}
+
+
+/// Shared by the `key arg path [path ...]` commands below: parses `arg` with `parse`
+/// and replies with the first path which exists in the key and satisfies `pred`, nil if there is none.
+fn first_existing<Q, P, F>(ctx: &Context, args: Vec<String>, parse: P, pred: F) -> RedisResult
+where
+    P: FnOnce(&str) -> Result<Q, RedisError>,
+    F: Fn(&Q, &LtreePath) -> bool,
+{
+    if args.len() < 4 {
+        return Err(RedisError::WrongArity);
+    }
+    let arg = parse(&args[2])?;
+    let paths = args[3..].iter().map(|path| parse_path(path)).collect::<Result<Vec<_>, _>>()?;
+
+    let key = ctx.open_key(&args[1]);
+    let found = key.get_value::<RedisTree>(&TREE_TYPE)?.and_then(|value| {
+        paths.into_iter().find(|path| pred(&arg, path) && value.get(path).is_some())
+    });
+
+    Ok(found.map_or(RedisValue::Null, |path| RedisValue::BulkString(path.to_string())))
+}
+
+/// TREE.FIRSTANCESTOR key path ancestor [ancestor ...]
+///
+/// Replies with the first `ancestor` which exists in the key and is `path` or one of its ancestors,
+/// nil if there is none: e.g. whether a resource is below any of the granted roots.
+pub fn tree_firstancestor(ctx: &Context, args: Vec<String>) -> RedisResult {
+    first_existing(ctx, args, parse_path, |path, ancestor| ancestor.is_ancestor_of(path))
+}
+
+/// TREE.ANYMATCH key lquery path [path ...]
+///
+/// Replies with the first `path` which exists in the key and matches `lquery`, nil if there is none.
+pub fn tree_anymatch(ctx: &Context, args: Vec<String>) -> RedisResult {
+    first_existing(ctx, args, parse_lquery, Lquery::is_match)
+}
+
+/// TREE.FIRSTDESCENDANT key path descendant [descendant ...]
+///
+/// Replies with the first `descendant` which exists in the key and is `path` or one of its descendants,
+/// nil if there is none, as ltree's `?<@`.
+pub fn tree_firstdescendant(ctx: &Context, args: Vec<String>) -> RedisResult {
+    first_existing(ctx, args, parse_path, LtreePath::is_ancestor_of)
+}
+
+/// TREE.ANYTXTMATCH key ltxtquery path [path ...]
+///
+/// Replies with the first `path` which exists in the key and matches `ltxtquery`, nil if there is none, as ltree's `?@`.
+pub fn tree_anytxtmatch(ctx: &Context, args: Vec<String>) -> RedisResult {
+    first_existing(ctx, args, parse_ltxtquery, Ltxtquery::is_match)
+}
+
+/// TREE.CHILDREN key path [DEPTH n]
+///
+/// Replies with the paths below `path` down to `DEPTH` levels, 1 by default
+/// for the children alone, in pre-order. A missing key or path has no children.
+pub fn tree_children(ctx: &Context, args: Vec<String>) -> RedisResult {
+    let mut args = args.into_iter().skip(1);
+    let key = args.next_string()?;
+    let path = parse_path(&args.next_string()?)?;
+    let depth = next_depth(&mut args)?.unwrap_or(1);
+    args.done()?;
+
+    let key = ctx.open_key(&key);
+    let node = key.get_value::<RedisTree>(&TREE_TYPE)?.and_then(|value| value.get(&path));
+    let children = match node {
+        Some(node) => node.dfs_paths()
+            .limit(Traversal::new().min_depth(1).max_depth(depth))
+            .map(|(relative, _)| {
+                let labels = path.iter().chain(relative.iter()).map(String::as_str).collect::<Vec<_>>();
+                RedisValue::BulkString(labels.join("."))
+            })
+            .collect(),
+        None => Vec::new(),
+    };
+
+    Ok(RedisValue::Array(children))
+}
@@ -0,0 +1,155 @@
+use std::os::raw::{c_int, c_void};
+use std::ptr;
+
+use fulltree::{Label, Tree};
+use redis_module::raw;
+
+use crate::{Record, RedisTree, Value};
+
+
+/// Version of the RDB layout below, bump it whenever the layout changes
+/// and keep loading the older versions.
+///
+/// v1: node count, then every node in pre-order as
+///     `label, field count, (field, value)*, child count`
+/// v2: as v1, each value being `type, payload` with the type codes below
+///     and no payload for null, an unsigned for bool, a signed for int,
+///     a double for float and a string for string. v1 values are typed on load.
+/// v3: rollup field count and the rollup fields, then as v2.
+///     Totals are not saved but computed on load.
+/// v4: as v3, with a numeric type whose payload is the text it was set from.
+pub const ENCODING_VERSION: i32 = 4;
+
+const TYPE_NULL: u64 = 0;
+const TYPE_BOOL: u64 = 1;
+const TYPE_INT: u64 = 2;
+const TYPE_FLOAT: u64 = 3;
+const TYPE_STRING: u64 = 4;
+const TYPE_NUMERIC: u64 = 5;
+
+
+pub unsafe extern "C" fn rdb_save(rdb: *mut raw::RedisModuleIO, value: *mut c_void) {
+    let value = &*(value as *mut RedisTree);
+    raw::save_unsigned(rdb, value.rollups.len() as u64);
+    for field in &value.rollups {
+        raw::save_string(rdb, field);
+    }
+
+    let root = value.tree.root();
+    raw::save_unsigned(rdb, root.node_count() as u64);
+
+    for node in root.walk().pre_order() {
+        save_record(rdb, &node.data);
+        raw::save_unsigned(rdb, node.degree() as u64);
+    }
+}
+
+pub unsafe extern "C" fn rdb_load(rdb: *mut raw::RedisModuleIO, encver: c_int) -> *mut c_void {
+    if !(1..=ENCODING_VERSION).contains(&encver) {
+        return ptr::null_mut();
+    }
+
+    let mut rollups = Vec::new();
+    if encver >= 3 {
+        for _ in 0..raw::load_unsigned(rdb) {
+            rollups.push(raw::load_string(rdb));
+        }
+    }
+
+    let node_count = raw::load_unsigned(rdb);
+    let tree = match load_tree(rdb, encver) {
+        Some(tree) => tree,
+        None => return ptr::null_mut(),
+    };
+    if tree.node_count() as u64 != node_count {
+        return ptr::null_mut();
+    }
+    Box::into_raw(Box::new(RedisTree::from_tree(tree, rollups))) as *mut c_void
+}
+
+
+fn save_record(rdb: *mut raw::RedisModuleIO, record: &Record) {
+    raw::save_string(rdb, record.label());
+    raw::save_unsigned(rdb, record.fields.len() as u64);
+    for (field, value) in &record.fields {
+        raw::save_string(rdb, field);
+        save_value(rdb, value);
+    }
+}
+
+fn save_value(rdb: *mut raw::RedisModuleIO, value: &Value) {
+    match value {
+        Value::Null => raw::save_unsigned(rdb, TYPE_NULL),
+        Value::Bool(boolean) => {
+            raw::save_unsigned(rdb, TYPE_BOOL);
+            raw::save_unsigned(rdb, *boolean as u64);
+        }
+        Value::Int(int) => {
+            raw::save_unsigned(rdb, TYPE_INT);
+            raw::save_signed(rdb, *int);
+        }
+        Value::Float(float) => {
+            raw::save_unsigned(rdb, TYPE_FLOAT);
+            raw::save_double(rdb, *float);
+        }
+        Value::Numeric(_, text) => {
+            raw::save_unsigned(rdb, TYPE_NUMERIC);
+            raw::save_string(rdb, text);
+        }
+        Value::String(string) => {
+            raw::save_unsigned(rdb, TYPE_STRING);
+            raw::save_string(rdb, string);
+        }
+    }
+}
+
+fn load_value(rdb: *mut raw::RedisModuleIO, encver: c_int) -> Option<Value> {
+    if encver < 2 {
+        return Some(Value::parse(raw::load_string(rdb)));
+    }
+    let value = match raw::load_unsigned(rdb) {
+        TYPE_NULL => Value::Null,
+        TYPE_BOOL => Value::Bool(raw::load_unsigned(rdb) != 0),
+        TYPE_INT => Value::Int(raw::load_signed(rdb)),
+        TYPE_FLOAT => Value::Float(raw::load_double(rdb)),
+        TYPE_STRING => Value::String(raw::load_string(rdb)),
+        TYPE_NUMERIC if encver >= 4 => Value::parse(raw::load_string(rdb)),
+        _ => return None,
+    };
+    Some(value)
+}
+
+fn load_node(rdb: *mut raw::RedisModuleIO, encver: c_int) -> Option<(Tree<Record>, u64)> {
+    let mut record = Record::from_label(&raw::load_string(rdb));
+    let field_count = raw::load_unsigned(rdb);
+    for _ in 0..field_count {
+        let field = raw::load_string(rdb);
+        let value = load_value(rdb, encver)?;
+        record.fields.insert(field, value);
+    }
+    let degree = raw::load_unsigned(rdb);
+    Some((Tree::new(record), degree))
+}
+
+/// Rebuilds the tree saved in pre-order: every node stays on the stack
+/// with the count of children still to be read, and is attached to its parent once complete.
+fn load_tree(rdb: *mut raw::RedisModuleIO, encver: c_int) -> Option<Tree<Record>> {
+    let mut stack = vec![load_node(rdb, encver)?];
+    loop {
+        let remaining = stack.last().map_or(0, |(_, remaining)| *remaining);
+        if remaining > 0 {
+            stack.push(load_node(rdb, encver)?);
+            continue;
+        }
+
+        // Wide nodes are indexed by `RedisTree::from_tree`.
+        let (tree, _) = stack.pop().expect("stack holds at least the root");
+        match stack.last_mut() {
+            Some((parent, remaining)) => {
+                parent.root_mut().get_mut().push_back(tree);
+                *remaining -= 1;
+            }
+            None => return Some(tree),
+        }
+    }
+}
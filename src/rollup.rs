@@ -0,0 +1,223 @@
+use std::convert::TryFrom;
+use std::ops::{AddAssign, Neg, Sub};
+
+use redis_module::{Context, NextArg, RedisError, RedisResult, RedisValue};
+
+use fulltree::{LtreePath, Node};
+
+use crate::fields::Fields;
+use crate::value::Value;
+use crate::{parse_path, replicate, Record, RedisTree, TREE_TYPE};
+
+
+/// A sum of rollup values, see `Record::totals`.
+///
+/// Ints are summed apart from floats, exactly: a total with no float in it is the same
+/// int whatever order it was summed in, whether updated in place or recomputed on load.
+/// Floats are summed as `f64` in the order the updates come, so a total with fractional
+/// floats in it may differ in its last bits from the same total recomputed on load.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Total {
+    ints: i128,
+    floats: f64,
+    float_count: i64, // floats in the sum, `floats` is reset to exactly 0 when none is left
+}
+
+impl Total {
+    fn of(value: Option<&Value>) -> Total {
+        match value {
+            Some(Value::Int(int)) => Total { ints: *int as i128, ..Total::default() },
+            Some(value) => match value.as_f64() {
+                Some(float) => Total { floats: float, float_count: 1, ..Total::default() },
+                None => Total::default(),
+            },
+            None => Total::default(),
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.ints == 0 && self.float_count == 0
+    }
+
+    /// Totals are replied as integers when integral, as float bulk strings otherwise.
+    fn reply(&self) -> RedisValue {
+        if self.float_count == 0 {
+            return match i64::try_from(self.ints) {
+                Ok(int) => RedisValue::Integer(int),
+                Err(_) => RedisValue::BulkString(self.ints.to_string()),
+            };
+        }
+        let total = self.ints as f64 + self.floats;
+        if total.fract() == 0.0 && total.abs() < (1u64 << 53) as f64 {
+            RedisValue::Integer(total as i64)
+        } else {
+            RedisValue::BulkString(Value::Float(total).to_string())
+        }
+    }
+}
+
+impl AddAssign for Total {
+    fn add_assign(&mut self, other: Total) {
+        self.ints += other.ints;
+        self.float_count += other.float_count;
+        self.floats = if self.float_count == 0 { 0.0 } else { self.floats + other.floats };
+    }
+}
+
+impl Neg for Total {
+    type Output = Total;
+    fn neg(self) -> Total {
+        Total { ints: -self.ints, floats: -self.floats, float_count: -self.float_count }
+    }
+}
+
+impl Sub for Total {
+    type Output = Total;
+    fn sub(mut self, other: Total) -> Total {
+        self += -other;
+        self
+    }
+}
+
+/// Values of the rollup fields in `fields`, 0 for missing or non numeric values.
+pub fn values(rollups: &[String], fields: &Fields) -> Vec<Total> {
+    rollups.iter()
+        .map(|field| Total::of(fields.get(field)))
+        .collect()
+}
+
+/// Recomputes the totals of `node` and all its descendants.
+///
+/// In breadth-first order the children of every node are contiguous and follow
+/// the children of the nodes visited before it, so the totals are summed up
+/// from the last node to the first.
+pub fn compute_totals(rollups: &[String], node: &mut Node<Record>) {
+    let (mut totals, degrees): (Vec<Vec<Total>>, Vec<usize>) = node.bfs().iter
+        .map(|visit| (values(rollups, &visit.data.fields), visit.size.degree as usize))
+        .unzip();
+
+    let mut first_child = Vec::with_capacity(degrees.len());
+    let mut next = 1;
+    for degree in &degrees {
+        first_child.push(next);
+        next += degree;
+    }
+    for i in (0..totals.len()).rev() {
+        let (head, tail) = totals.split_at_mut(first_child[i]);
+        for child in &tail[..degrees[i]] {
+            for (total, value) in head[i].iter_mut().zip(child) {
+                *total += *value;
+            }
+        }
+    }
+
+    for (visit, total) in node.bfs_mut().iter.zip(totals) {
+        visit.data.totals = total;
+    }
+}
+
+impl RedisTree {
+    /// Adds `deltas` to the totals of the nodes from the root down to `path`.
+    pub(crate) fn add_totals(&mut self, path: &LtreePath, deltas: &[Total]) {
+        if deltas.iter().all(Total::is_zero) {
+            return;
+        }
+        let mut node = self.tree.root_mut().get_mut();
+        add(&mut node.data.totals, deltas);
+        for label in path {
+            node = node.child_mut(label).expect("path exists").get_mut();
+            add(&mut node.data.totals, deltas);
+        }
+    }
+
+    fn add_rollup(&mut self, field: String) -> bool {
+        if self.rollups.contains(&field) {
+            return false;
+        }
+        self.rollups.push(field);
+        compute_totals(&self.rollups, self.tree.root_mut().get_mut());
+        true
+    }
+
+    fn remove_rollup(&mut self, field: &str) -> bool {
+        match self.rollups.iter().position(|rollup| rollup == field) {
+            Some(position) => {
+                self.rollups.remove(position);
+                compute_totals(&self.rollups, self.tree.root_mut().get_mut());
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+fn add(totals: &mut Vec<Total>, deltas: &[Total]) {
+    if totals.len() < deltas.len() {
+        totals.resize(deltas.len(), Total::default());
+    }
+    for (total, delta) in totals.iter_mut().zip(deltas) {
+        *total += *delta;
+    }
+}
+
+/// TREE.ROLLUP key ADD field SUM | TREE.ROLLUP key DEL field | TREE.ROLLUP key LIST
+///
+/// Maintains for every node the sum of a numeric field over its subtree, the node included,
+/// so that reading it with `TREE.TOTAL` is a path lookup.
+/// ADD and DEL reply with 1 when the rollups changed, 0 otherwise.
+pub fn tree_rollup(ctx: &Context, args: Vec<String>) -> RedisResult {
+    let mut args = args.into_iter().skip(1);
+    let key = args.next_string()?;
+    let subcommand = args.next_string()?.to_ascii_uppercase();
+
+    match subcommand.as_str() {
+        "ADD" | "DEL" => {
+            let field = args.next_string()?;
+            if subcommand == "ADD" && !args.next_string()?.eq_ignore_ascii_case("SUM") {
+                return Err(RedisError::Str("ERR only SUM rollups are supported"));
+            }
+            args.done()?;
+
+            let redis_key = ctx.open_key_writable(&key);
+            let value = redis_key.get_value::<RedisTree>(&TREE_TYPE)?.ok_or(RedisError::Str("ERR no such key"))?;
+            let changed = match subcommand.as_str() {
+                "ADD" => value.add_rollup(field),
+                _ => value.remove_rollup(&field),
+            };
+            if changed {
+                replicate(ctx, "tree.rollup", &key);
+            }
+            Ok((changed as i64).into())
+        }
+        "LIST" => {
+            args.done()?;
+            let redis_key = ctx.open_key(&key);
+            let rollups = match redis_key.get_value::<RedisTree>(&TREE_TYPE)? {
+                Some(value) => value.rollups.iter().cloned().map(RedisValue::BulkString).collect(),
+                None => Vec::new(),
+            };
+            Ok(RedisValue::Array(rollups))
+        }
+        _ => Err(RedisError::Str("ERR syntax error")),
+    }
+}
+
+/// TREE.TOTAL key path field
+///
+/// Replies with the total of a rollup field over the subtree at `path`,
+/// nil if there is no such path or `field` has no rollup.
+pub fn tree_total(ctx: &Context, args: Vec<String>) -> RedisResult {
+    let mut args = args.into_iter().skip(1);
+    let key = args.next_string()?;
+    let path = parse_path(&args.next_string()?)?;
+    let field = args.next_string()?;
+    args.done()?;
+
+    let key = ctx.open_key(&key);
+    let total = key.get_value::<RedisTree>(&TREE_TYPE)?.and_then(|value| {
+        let position = value.rollups.iter().position(|rollup| *rollup == field)?;
+        let node = value.get(&path)?;
+        Some(node.data.totals.get(position).cloned().unwrap_or_default())
+    });
+    Ok(total.map_or(RedisValue::Null, |total| total.reply()))
+}
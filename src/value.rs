@@ -0,0 +1,121 @@
+use std::cmp::Ordering;
+use std::fmt::{self, Display, Formatter};
+use std::hash::{Hash, Hasher};
+
+
+/// A typed field value.
+///
+/// Values are typed on set from their text: `null`, `true`, `false` and any finite number are recognized,
+/// anything else stays a string, as do numbers with leading zeros such as `007`.
+/// Numbers not written the way they are displayed, e.g. `1.50` or `1e3`, keep their text,
+/// so displaying a value gives back exactly the text it was set from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    /// A float and the text it was set from.
+    Numeric(f64, String),
+    String(String),
+}
+
+impl Value {
+    pub fn parse(text: String) -> Value {
+        match text.as_str() {
+            "null" => return Value::Null,
+            "true" => return Value::Bool(true),
+            "false" => return Value::Bool(false),
+            _ => (),
+        }
+        if let Ok(int) = text.parse::<i64>() {
+            if int.to_string() == text {
+                return Value::Int(int);
+            }
+        }
+        if let Ok(float) = text.parse::<f64>() {
+            if float.is_finite() && !has_leading_zero(&text) {
+                if format!("{:?}", float) == text {
+                    return Value::Float(float);
+                }
+                return Value::Numeric(float, text);
+            }
+        }
+        Value::String(text)
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Null => "null",
+            Value::Bool(_) => "bool",
+            Value::Int(_) => "int",
+            Value::Float(_) | Value::Numeric(..) => "float",
+            Value::String(_) => "string",
+        }
+    }
+
+    /// The value as a number, for ints and floats only.
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Value::Int(int) => Some(int as f64),
+            Value::Float(float) | Value::Numeric(float, _) => Some(float),
+            _ => None,
+        }
+    }
+
+    /// Compares numbers numerically and strings lexicographically,
+    /// values of other or mixed types are not comparable.
+    pub fn compare(&self, other: &Value) -> Option<Ordering> {
+        match (self, other) {
+            (Value::Int(lhs), Value::Int(rhs)) => Some(lhs.cmp(rhs)),
+            (Value::String(lhs), Value::String(rhs)) => Some(lhs.cmp(rhs)),
+            (Value::Bool(lhs), Value::Bool(rhs)) => Some(lhs.cmp(rhs)),
+            _ => self.as_f64()?.partial_cmp(&other.as_f64()?),
+        }
+    }
+
+    pub fn heap_size(&self) -> usize {
+        match self {
+            Value::String(string) | Value::Numeric(_, string) => string.capacity(),
+            _ => 0,
+        }
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Value::Null => f.write_str("null"),
+            Value::Bool(boolean) => write!(f, "{}", boolean),
+            Value::Int(int) => write!(f, "{}", int),
+            // `{:?}` keeps the fraction of integral floats, e.g. `3.0`, so they stay floats.
+            Value::Float(float) => write!(f, "{:?}", float),
+            Value::Numeric(_, text) => f.write_str(text),
+            Value::String(string) => f.write_str(string),
+        }
+    }
+}
+
+// Floats are always finite, see `Value::parse`.
+impl Eq for Value {}
+
+impl Hash for Value {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.type_name().hash(state);
+        match self {
+            Value::Null => (),
+            Value::Bool(boolean) => boolean.hash(state),
+            Value::Int(int) => int.hash(state),
+            Value::Float(float) => float.to_bits().hash(state),
+            Value::Numeric(_, text) => text.hash(state),
+            Value::String(string) => string.hash(state),
+        }
+    }
+}
+
+
+/// Whether the integer part of numeric text has a leading zero, as in `007` or `-01.5`.
+fn has_leading_zero(text: &str) -> bool {
+    let digits = text.trim_start_matches(['+', '-']).as_bytes();
+    digits.len() > 1 && digits[0] == b'0' && digits[1].is_ascii_digit()
+}
@@ -0,0 +1,415 @@
+import time
+
+import pytest
+import redis
+
+
+def test_set_and_get(redis_client):
+    assert redis_client.execute_command("tree.set", "tree", "top", "field1", "value1", "field2", "2")
+    assert redis_client.execute_command("tree.get", "tree", "top") == ["field1", "value1", "field2", "2"]
+
+
+def test_set_creates_intermediate_nodes(redis_client):
+    redis_client.execute_command("tree.set", "tree", "top.x.y", "field1", "value1")
+    assert redis_client.execute_command("tree.get", "tree", "top.x") == []
+    assert redis_client.execute_command("tree.get", "tree", "top.x.y") == ["field1", "value1"]
+    assert redis_client.execute_command("tree.get", "tree", "top.z") is None
+
+
+def test_del_subtree(redis_client):
+    redis_client.execute_command("tree.set", "tree", "top.x", "field1", "value1")
+    redis_client.execute_command("tree.set", "tree", "top.y", "field1", "value1")
+    assert redis_client.execute_command("tree.del", "tree", "top.x") == 1
+    assert redis_client.execute_command("tree.get", "tree", "top.x") is None
+    assert redis_client.execute_command("tree.del", "tree", "top") == 2
+    assert redis_client.exists("tree") == 0
+
+
+def test_del_keeps_root_fields(redis_client):
+    redis_client.execute_command("tree.set", "tree", "", "f", "root")
+    redis_client.execute_command("tree.set", "tree", "a", "f", "a")
+    assert redis_client.execute_command("tree.del", "tree", "a") == 1
+    assert redis_client.execute_command("tree.get", "tree", "") == ["f", "root"]
+    assert redis_client.execute_command("tree.del", "tree", "") == 0
+    assert redis_client.exists("tree") == 0
+
+
+def test_del_modes(redis_client):
+    for path in ["top.a", "top.b.x", "top.b.y", "top.c", "top.x"]:
+        redis_client.execute_command("tree.set", "tree", path, "f", path)
+    with pytest.raises(redis.exceptions.ResponseError):
+        redis_client.execute_command("tree.del", "tree", "top.b", "ORPHAN")
+    with pytest.raises(redis.exceptions.ResponseError):
+        redis_client.execute_command("tree.del", "tree", "top.b", "LIFT")
+    redis_client.execute_command("tree.del", "tree", "top.x")
+    assert redis_client.execute_command("tree.del", "tree", "top.b", "lift") == 1
+    reply = redis_client.execute_command("tree.query", "tree", "~ top.*{1}")
+    assert [path for path, _ in reply] == ["top.a", "top.x", "top.y", "top.c"]
+    assert redis_client.execute_command("tree.del", "tree", "top.a", "ORPHAN") == 1
+    assert redis_client.execute_command("tree.del", "tree", "top", "CASCADE") == 4
+
+
+def test_invalid_path(redis_client):
+    for path in ["top..x", ".top", "top.", "top.x y"]:
+        with pytest.raises(redis.exceptions.ResponseError):
+            redis_client.execute_command("tree.set", "tree", path, "field1", "value1")
+
+
+def setup_astronomy(redis_client):
+    for path in ["Top.Science.Astronomy.Astrophysics",
+                 "Top.Science.Astronomy.Cosmology",
+                 "Top.Hobbies.Amateurs_Astronomy"]:
+        redis_client.execute_command("tree.set", "tree", path, "name", path.split(".")[-1])
+
+
+def query_paths(redis_client, *query):
+    return [path for path, _ in redis_client.execute_command("tree.query", "tree", *query)]
+
+
+def test_query_descendants(redis_client):
+    setup_astronomy(redis_client)
+    assert query_paths(redis_client, "<@ Top.Science") == [
+        "Top.Science",
+        "Top.Science.Astronomy",
+        "Top.Science.Astronomy.Astrophysics",
+        "Top.Science.Astronomy.Cosmology",
+    ]
+
+
+def test_query_ancestors(redis_client):
+    setup_astronomy(redis_client)
+    assert query_paths(redis_client, "@>", "Top.Science.Astronomy.Cosmology") == [
+        "Top", "Top.Science", "Top.Science.Astronomy", "Top.Science.Astronomy.Cosmology",
+    ]
+
+
+def test_query_lquery(redis_client):
+    setup_astronomy(redis_client)
+    reply = redis_client.execute_command("tree.query", "tree", "~  *.Astronomy.Cosmology")
+    assert reply == [["Top.Science.Astronomy.Cosmology", ["name", "Cosmology"]]]
+    assert query_paths(redis_client, "?", "*.Cosmology", "*.Amateurs%") == [
+        "Top.Science.Astronomy.Cosmology", "Top.Hobbies.Amateurs_Astronomy",
+    ]
+
+
+def test_query_ltxtquery(redis_client):
+    setup_astronomy(redis_client)
+    assert query_paths(redis_client, "@ Astronomy% & !Science") == ["Top.Hobbies.Amateurs_Astronomy"]
+    assert query_paths(redis_client, "@", "cosmo*@") == ["Top.Science.Astronomy.Cosmology"]
+
+
+def test_rdb_reload(redis_client):
+    setup_astronomy(redis_client)
+    redis_client.execute_command("tree.set", "tree", "", "root", "yes")
+    before = redis_client.execute_command("tree.query", "tree", "<@ Top")
+    redis_client.execute_command("debug", "reload")
+    assert redis_client.execute_command("tree.query", "tree", "<@ Top") == before
+    assert redis_client.execute_command("tree.get", "tree", "") == ["root", "yes"]
+
+
+def test_mset(redis_client):
+    assert redis_client.execute_command(
+        "tree.mset", "tree", "a.b", 1, "x", "1", "a.c", 0, "a", 2, "y", "2", "y", "3") == "OK"
+    assert redis_client.execute_command("tree.get", "tree", "a") == ["y", "3"]
+    assert redis_client.execute_command("tree.get", "tree", "a.c") == []
+    with pytest.raises(redis.ResponseError):
+        redis_client.execute_command("tree.mset", "tree", "a.d", 1, "x")
+    assert redis_client.execute_command("tree.get", "tree", "a.d") is None
+    with pytest.raises(redis.ResponseError):
+        redis_client.execute_command("tree.mset", "tree", "a.d", 1000000000000)
+
+
+def test_aof_rewrite(redis_client):
+    setup_astronomy(redis_client)
+    redis_client.execute_command("tree.mset", "empty", "", 0)
+    redis_client.execute_command("tree.rollup", "empty", "ADD", "f", "SUM")
+    before = redis_client.execute_command("tree.query", "tree", "<@ Top")
+    # turning appendonly on starts the rewrite by itself
+    redis_client.config_set("appendonly", "yes")
+    try:
+        wait_for_aof_rewrite(redis_client)
+        redis_client.execute_command("debug", "loadaof")
+        assert redis_client.execute_command("tree.query", "tree", "<@ Top") == before
+        assert redis_client.execute_command("tree.rollup", "empty", "LIST") == ["f"]
+    finally:
+        redis_client.config_set("appendonly", "no")
+
+
+def wait_for_aof_rewrite(redis_client, timeout=10):
+    deadline = time.monotonic() + timeout
+    while True:
+        persistence = redis_client.info("persistence")
+        if not persistence["aof_rewrite_in_progress"] and not persistence.get("aof_rewrite_scheduled"):
+            return
+        assert time.monotonic() < deadline, "AOF rewrite did not finish"
+        time.sleep(0.05)
+
+
+def test_memory_usage(redis_client):
+    redis_client.execute_command("tree.set", "tree", "a", "f", "v")
+    small = redis_client.memory_usage("tree")
+    redis_client.execute_command("tree.set", "tree", "a.b.c", "field", "x" * 1000)
+    large = redis_client.memory_usage("tree")
+    assert large > small + 1000
+    redis_client.execute_command("tree.del", "tree", "a.b")
+    assert redis_client.memory_usage("tree") == small
+    redis_client.execute_command("debug", "reload")
+    assert redis_client.memory_usage("tree") >= small
+
+
+def test_digest(redis_client):
+    redis_client.execute_command("tree.set", "t1", "a.b", "f", "1")
+    redis_client.execute_command("tree.set", "t1", "a.c", "f", "2")
+    redis_client.execute_command("tree.mset", "t2", "a.b", 1, "f", "1", "a.c", 1, "f", "2")
+    redis_client.execute_command("tree.set", "t3", "a.b.c", "f", "1")
+    digest = lambda key: redis_client.execute_command("debug", "digest-value", key)
+    assert digest("t1") == digest("t2")
+    assert digest("t1") != digest("t3")
+    before = redis_client.execute_command("debug", "digest")
+    redis_client.execute_command("debug", "reload")
+    assert redis_client.execute_command("debug", "digest") == before
+
+
+def test_keyspace_events(redis_client):
+    redis_client.config_set("notify-keyspace-events", "KEd")
+    pubsub = redis_client.pubsub()
+    pubsub.psubscribe("__keyevent@*__:tree.*")
+    pubsub.get_message(timeout=1)
+    redis_client.execute_command("tree.set", "tree", "a.b", "f", "v")
+    redis_client.execute_command("tree.del", "tree", "a.x")
+    redis_client.execute_command("tree.del", "tree", "a.b")
+    events = []
+    while True:
+        message = pubsub.get_message(timeout=1)
+        if message is None:
+            break
+        events.append((message["channel"].split(":")[-1], message["data"]))
+    assert events == [("tree.set", "tree"), ("tree.del", "tree")]
+
+
+def test_move(redis_client):
+    setup_astronomy(redis_client)
+    redis_client.execute_command("tree.set", "tree", "Top.Archive", "name", "Archive")
+    before = len(redis_client.execute_command("tree.query", "tree", "<@ Top"))
+    assert redis_client.execute_command("tree.move", "tree", "Top.Science", "Top.Archive") == "OK"
+    assert redis_client.execute_command("tree.get", "tree", "Top.Science") is None
+    assert redis_client.execute_command("tree.get", "tree", "Top.Archive.Science.Astronomy.Cosmology") is not None
+    assert len(redis_client.execute_command("tree.query", "tree", "<@ Top")) == before
+
+    with pytest.raises(redis.exceptions.ResponseError):
+        redis_client.execute_command("tree.move", "tree", "Top.Archive", "Top.Archive.Science")
+    with pytest.raises(redis.exceptions.ResponseError):
+        redis_client.execute_command("tree.move", "tree", "Top.Archive", "Top", "BEFORE", "Nope")
+
+    redis_client.execute_command("tree.move", "tree", "Top.Archive", "Top", "BEFORE", "Hobbies")
+    reply = redis_client.execute_command("tree.query", "tree", "~ Top.*{1}")
+    assert [path for path, _ in reply] == ["Top.Archive", "Top.Hobbies"]
+
+
+def test_copy(redis_client):
+    redis_client.execute_command("tree.set", "template", "Dept.Staff", "role", "staff")
+    redis_client.execute_command("tree.set", "template", "Dept.Budget", "amount", "0")
+    for parent in ["Org.Sales", "Org.Support"]:
+        assert redis_client.execute_command("tree.copy", "template", "Dept", "org", parent + ".Dept") == 1
+    assert redis_client.execute_command("tree.get", "org", "Org.Support.Dept.Staff") == ["role", "staff"]
+
+    assert redis_client.execute_command("tree.copy", "template", "Dept.Staff", "org", "Org.Sales.Dept.Budget") == 0
+    assert redis_client.execute_command(
+        "tree.copy", "template", "Dept.Staff", "org", "Org.Sales.Dept.Budget", "REPLACE") == 1
+    assert redis_client.execute_command("tree.get", "org", "Org.Sales.Dept.Budget") == ["role", "staff"]
+    reply = redis_client.execute_command("tree.query", "org", "~ Org.Sales.Dept.*{1}")
+    assert [path for path, _ in reply] == ["Org.Sales.Dept.Staff", "Org.Sales.Dept.Budget"]
+
+    assert redis_client.execute_command("tree.copy", "org", "Org", "org", "Org.Sales.Dept.Backup") == 1
+    assert redis_client.execute_command("tree.get", "org", "Org.Sales.Dept.Backup.Support.Dept.Staff") == ["role", "staff"]
+    assert redis_client.execute_command("tree.copy", "template", "Missing", "org", "Org.X") == 0
+
+
+def test_hash_commands(redis_client):
+    assert redis_client.execute_command("tree.hset", "tree", "a.b", "x", "1", "y", "2") == 2
+    assert redis_client.execute_command("tree.hset", "tree", "a.b", "x", "3", "z", "4") == 1
+    assert redis_client.execute_command("tree.hgetall", "tree", "a.b") == ["x", "3", "y", "2", "z", "4"]
+    assert redis_client.execute_command("tree.hget", "tree", "a.b", "y") == "2"
+    assert redis_client.execute_command("tree.hget", "tree", "a.b", "w") is None
+    assert redis_client.execute_command("tree.hdel", "tree", "a.b", "y", "w") == 1
+    assert redis_client.execute_command("tree.hgetall", "tree", "a.b") == ["x", "3", "z", "4"]
+    assert redis_client.execute_command("tree.hgetall", "tree", "a.c") == []
+    assert redis_client.execute_command("tree.hincrby", "tree", "a.b", "x", 5) == 8
+    assert redis_client.execute_command("tree.hincrby", "tree", "a.c", "n", -2) == -2
+    redis_client.execute_command("tree.hset", "tree", "a.b", "s", "text")
+    with pytest.raises(redis.exceptions.ResponseError):
+        redis_client.execute_command("tree.hincrby", "tree", "a.b", "s", 1)
+
+
+def test_many_fields(redis_client):
+    pairs = [item for i in range(100) for item in ("f%d" % i, str(i))]
+    assert redis_client.execute_command("tree.hset", "tree", "a", *pairs, "f7", "x") == 100
+    assert redis_client.execute_command("tree.hdel", "tree", "a", "f0", "f50") == 2
+    assert redis_client.execute_command("tree.hget", "tree", "a", "f7") == "x"
+    assert redis_client.execute_command("tree.hget", "tree", "a", "f99") == "99"
+    assert redis_client.execute_command("tree.hget", "tree", "a", "f50") is None
+    fields = redis_client.execute_command("tree.hgetall", "tree", "a")[::2]
+    assert fields == ["f%d" % i for i in range(100) if i not in (0, 50)]
+
+
+def test_hdel_last_root_field_deletes_key(redis_client):
+    redis_client.execute_command("tree.hset", "tree", "", "f", "1", "g", "2")
+    assert redis_client.execute_command("tree.hdel", "tree", "", "f") == 1
+    assert redis_client.execute_command("exists", "tree") == 1
+    assert redis_client.execute_command("tree.hdel", "tree", "", "g") == 1
+    assert redis_client.execute_command("exists", "tree") == 0
+    redis_client.execute_command("tree.set", "tree", "a", "f", "1")
+    redis_client.execute_command("tree.hset", "tree", "", "f", "1")
+    assert redis_client.execute_command("tree.hdel", "tree", "", "f") == 1
+    assert redis_client.execute_command("tree.hdel", "tree", "a", "f") == 1
+    assert redis_client.execute_command("tree.get", "tree", "a") == []
+
+
+def test_typed_values(redis_client):
+    redis_client.execute_command(
+        "tree.set", "tree", "a", "i", "2", "f", "2.5", "b", "true", "n", "null", "s", "text", "z", "007")
+    types = [redis_client.execute_command("tree.htype", "tree", "a", field) for field in "ifbnsz"]
+    assert types == ["int", "float", "bool", "null", "string", "string"]
+    assert redis_client.execute_command("tree.get", "tree", "a") == [
+        "i", "2", "f", "2.5", "b", "true", "n", "null", "s", "text", "z", "007"]
+    assert redis_client.execute_command("tree.hincrbyfloat", "tree", "a", "i", "1") == "3.0"
+    assert redis_client.execute_command("tree.htype", "tree", "a", "i") == "float"
+    with pytest.raises(redis.exceptions.ResponseError):
+        redis_client.execute_command("tree.hincrby", "tree", "a", "f", 1)
+    redis_client.execute_command("debug", "reload")
+    assert redis_client.execute_command("tree.htype", "tree", "a", "i") == "float"
+    assert redis_client.execute_command("tree.hget", "tree", "a", "i") == "3.0"
+
+
+def test_numeric_text(redis_client):
+    texts = {"p": "19.90", "q": "1.50", "e": "1e3", "m": "-0", "z": "007", "x": "1e999"}
+    redis_client.execute_command("tree.hset", "tree", "a", *[item for pair in texts.items() for item in pair])
+    types = [redis_client.execute_command("tree.htype", "tree", "a", field) for field in "peqmzx"]
+    assert types == ["float", "float", "float", "float", "string", "string"]
+    redis_client.execute_command("debug", "reload")
+    assert {field: redis_client.execute_command("tree.hget", "tree", "a", field) for field in texts} == texts
+    assert redis_client.execute_command("tree.htype", "tree", "a", "q") == "float"
+    assert redis_client.execute_command("tree.hincrbyfloat", "tree", "a", "q", "1") == "2.5"
+
+
+def test_failed_increment_creates_nothing(redis_client):
+    with pytest.raises(redis.exceptions.ResponseError):
+        redis_client.execute_command("tree.hincrbyfloat", "new", "a.b", "f", "inf")
+    assert redis_client.execute_command("exists", "new") == 0
+    redis_client.execute_command("tree.set", "tree", "a", "s", "text")
+    with pytest.raises(redis.exceptions.ResponseError):
+        redis_client.execute_command("tree.hincrbyfloat", "tree", "a.b", "f", "inf")
+    assert redis_client.execute_command("tree.get", "tree", "a.b") is None
+
+
+def test_agg(redis_client):
+    redis_client.execute_command("tree.set", "org", "Org", "headcount", "2")
+    redis_client.execute_command("tree.set", "org", "Org.Sales", "headcount", "10")
+    redis_client.execute_command("tree.set", "org", "Org.Sales.East", "headcount", "4")
+    redis_client.execute_command("tree.set", "org", "Org.Support", "headcount", "1.5")
+    redis_client.execute_command("tree.set", "org", "Org.Legal", "headcount", "unknown")
+    agg = lambda *args: redis_client.execute_command("tree.agg", "org", *args)
+    assert agg("Org.Sales", "headcount", "SUM") == 14
+    assert agg("Org", "headcount", "sum") == "17.5"
+    assert agg("Org", "headcount", "COUNT") == 4
+    assert agg("Org", "headcount", "MIN") == "1.5"
+    assert agg("Org", "headcount", "MAX") == 10
+    assert agg("Org", "headcount", "SUM", "DEPTH", 1) == "13.5"
+    assert agg("Org", "headcount", "AVG", "DEPTH", 0) == "2.0"
+    assert agg("Org.Missing", "headcount", "SUM") == 0
+    assert agg("Org.Missing", "headcount", "AVG") is None
+
+
+def test_rollup(redis_client):
+    redis_client.execute_command("tree.set", "org", "Org.Sales.East", "budget", "10")
+    redis_client.execute_command("tree.set", "org", "Org.Sales.West", "budget", "5")
+    redis_client.execute_command("tree.set", "org", "Org.Support", "budget", "2.5")
+    assert redis_client.execute_command("tree.rollup", "org", "ADD", "budget", "SUM") == 1
+    assert redis_client.execute_command("tree.rollup", "org", "LIST") == ["budget"]
+    total = lambda path: redis_client.execute_command("tree.total", "org", path, "budget")
+    assert total("Org") == "17.5"
+    assert total("Org.Sales") == 15
+
+    redis_client.execute_command("tree.hincrby", "org", "Org.Sales.East", "budget", 5)
+    redis_client.execute_command("tree.set", "org", "Org.Sales", "budget", "1")
+    assert total("Org.Sales") == 21
+    redis_client.execute_command("tree.move", "org", "Org.Sales.West", "Org.Support")
+    assert (total("Org.Sales"), total("Org.Support"), total("Org")) == (16, "7.5", "23.5")
+    redis_client.execute_command("tree.copy", "org", "Org.Sales", "org", "Org.Support.Sales2")
+    assert total("Org.Support") == "23.5"
+    redis_client.execute_command("tree.del", "org", "Org.Support", "LIFT")
+    assert total("Org") == 37
+    redis_client.execute_command("tree.del", "org", "Org.Sales")
+    assert total("Org") == 21
+
+    redis_client.execute_command("debug", "reload")
+    assert total("Org") == 21
+    assert redis_client.execute_command("tree.rollup", "org", "DEL", "budget") == 1
+    assert total("Org") is None
+
+
+def test_rollup_totals_after_reload(redis_client):
+    total = lambda path: redis_client.execute_command("tree.total", "t", path, "n")
+    redis_client.execute_command("tree.rollup", "t", "ADD", "n", "SUM")
+    redis_client.execute_command("tree.set", "t", "a.x", "n", str(2**60 + 1))
+    redis_client.execute_command("tree.set", "t", "a.y", "n", "0.1")
+    redis_client.execute_command("tree.set", "t", "a.z", "n", "1")
+    redis_client.execute_command("tree.set", "t", "a.y", "n", "2")
+    assert total("a") == 2**60 + 4
+    # Fractional totals are updated in place, they only match a recomputed total approximately.
+    for i, path in enumerate(["b.x", "b.y", "b.z", "b.x", "b.w"]):
+        redis_client.execute_command("tree.set", "t", path, "n", str(0.1 * (i + 1)))
+    before = float(total("b"))
+    redis_client.execute_command("debug", "reload")
+    assert total("a") == 2**60 + 4
+    assert float(total("b")) == pytest.approx(before, rel=1e-12)
+    assert before == pytest.approx(0.2 + 0.3 + 0.4 + 0.5)
+
+
+def test_ltree_functions(redis_client):
+    cmd = redis_client.execute_command
+    assert cmd("tree.nlevel", "Top.Child1.Child2") == 3
+    assert cmd("tree.subltree", "Top.Child1.Child2", 1, 2) == "Child1"
+    assert cmd("tree.subpath", "Top.Child1.Child2", 0, 2) == "Top.Child1"
+    assert cmd("tree.subpath", "Top.Child1.Child2", 1) == "Child1.Child2"
+    assert cmd("tree.subpath", "Top.Child1.Child2", -2, -1) == "Child1"
+    assert cmd("tree.subpath", "Top.Child1.Child2", 1, 2**63 - 1) == "Child1.Child2"
+    for offset, length in [(2**63 - 1, 2**63 - 1), (-2**63, 2**63 - 1), (0, -2**63)]:
+        with pytest.raises(redis.exceptions.ResponseError):
+            cmd("tree.subpath", "Top.Child1.Child2", offset, length)
+    assert cmd("tree.index", "0.1.2.3.5.4.5.6.8.5.6.8", "5.6") == 6
+    assert cmd("tree.index", "0.1.2.3.5.4.5.6.8.5.6.8", "5.6", -4) == 9
+    assert cmd("tree.index", "0.1.2", "5.6") == -1
+    assert cmd("tree.lca", "1.2.3", "1.2.3.4.5.6") == "1.2"
+    assert cmd("tree.lca", "1.2", "") is None
+    with pytest.raises(redis.exceptions.ResponseError):
+        cmd("tree.subltree", "Top.Child1.Child2", 2, 1)
+
+
+def test_first_ancestor_and_any_match(redis_client):
+    setup_astronomy(redis_client)
+    cmd = redis_client.execute_command
+    resource = "Top.Science.Astronomy.Cosmology"
+    assert cmd("tree.firstancestor", "tree", resource, "Top.Hobbies", "Top.Science", "Top") == "Top.Science"
+    assert cmd("tree.firstancestor", "tree", resource, "Top.Hobbies", "Top.Science.Missing") is None
+    assert cmd("tree.firstancestor", "tree", "Top.Nowhere", "Top.Nowhere", "Top") == "Top"
+    assert cmd("tree.anymatch", "tree", "*.Astronomy.*", "Top.Hobbies", resource) == resource
+    assert cmd("tree.anymatch", "tree", "*.Astronomy.*", "Top.Other.Astronomy.X") is None
+    assert cmd("tree.firstdescendant", "tree", "Top.Science", "Top.Hobbies", resource, "Top.Science") == resource
+    assert cmd("tree.firstdescendant", "tree", "Top.Science", "Top", "Top.Science.Missing") is None
+    assert cmd("tree.anytxtmatch", "tree", "Astronomy & !Stars", "Top.Hobbies", resource) == resource
+    assert cmd("tree.anytxtmatch", "tree", "Cosmology", "Top.Other.Cosmology") is None
+
+
+def test_children(redis_client):
+    setup_astronomy(redis_client)
+    cmd = redis_client.execute_command
+    assert cmd("tree.children", "tree", "Top") == ["Top.Science", "Top.Hobbies"]
+    assert cmd("tree.children", "tree", "Top", "DEPTH", 2) == [
+        "Top.Science",
+        "Top.Science.Astronomy",
+        "Top.Hobbies",
+        "Top.Hobbies.Amateurs_Astronomy",
+    ]
+    assert cmd("tree.children", "tree", "", "depth", 1) == ["Top"]
+    assert cmd("tree.children", "tree", "Top.Missing") == []
@@ -0,0 +1,143 @@
+use super::{Node, Tree, Label, Signature};
+use super::rust::*;
+
+
+/// A view into a single node of a tree addressed by a label path,
+/// which may either exist or be missing.
+///
+/// Built by [`Node::entry`](struct.Node.html#method.entry) and [`Tree::entry`](struct.Tree.html#method.entry).
+pub enum Entry<'a, T:'a> {
+    Occupied( Pin<&'a mut Node<T>> ),
+    Vacant( VacantEntry<'a, T> ),
+}
+
+/// The missing part of a path: the deepest node that exists,
+/// and the labels still to be created under it.
+pub struct VacantEntry<'a, T:'a> {
+    node   : Pin<&'a mut Node<T>>,
+    labels : Vec<String>,
+}
+
+impl<'a, T:'a + Label> VacantEntry<'a, T> {
+    #[inline] pub(crate) fn new( node: Pin<&'a mut Node<T>>, labels: Vec<String> ) -> Self {
+        VacantEntry{ node, labels }
+    }
+
+    /// The deepest existing node on the path.
+    #[inline] pub fn parent( &self ) -> &Node<T> { &self.node }
+
+    /// Labels that do not exist yet, the last one is the target node.
+    #[inline] pub fn labels( &self ) -> &[String] { &self.labels }
+
+    /// Creates the missing intermediate nodes with `Label::from_label`,
+    /// then attaches `data` as the target node.
+    ///
+    /// # Panics
+    ///
+    /// If `data` is not labeled with the last label of the path.
+    pub fn insert( self, data: T ) -> Pin<&'a mut Node<T>> {
+        let ( label, parents ) = self.labels.split_last().expect( "vacant entry without labels" );
+        assert_eq!( data.label(), label, "data must be labeled with the last label of the path" );
+        unsafe {
+            let parent = self.node.get_unchecked_mut() as *mut Node<T>;
+            let mut node = parent;
+            for label in parents {
+                (*node).push_back( Tree::labeled( T::from_label( label )));
+                node = (*node).tail() as *mut Node<T>;
+            }
+            (*node).push_back( Tree::labeled( data ));
+            (*parent).build_index_if_wide();
+            Pin::new_unchecked( &mut *( (*node).tail() as *mut Node<T> ))
+        }
+    }
+}
+
+impl<'a, T:'a + Label> Entry<'a, T> {
+    /// Returns the target node, inserting `data` if it is missing.
+    #[inline] pub fn or_insert( self, data: T ) -> Pin<&'a mut Node<T>> {
+        match self {
+            Entry::Occupied( node ) => node,
+            Entry::Vacant( entry ) => entry.insert( data ),
+        }
+    }
+
+    #[inline] pub fn or_insert_with<F:FnOnce() -> T>( self, f: F ) -> Pin<&'a mut Node<T>> {
+        match self {
+            Entry::Occupied( node ) => node,
+            Entry::Vacant( entry ) => entry.insert( f() ),
+        }
+    }
+
+    /// Returns the target node, creating it like an intermediate node if it is missing.
+    #[inline] pub fn or_create( self ) -> Pin<&'a mut Node<T>> {
+        match self {
+            Entry::Occupied( node ) => node,
+            Entry::Vacant( entry ) => {
+                let data = T::from_label( entry.labels.last().expect( "vacant entry without labels" ));
+                entry.insert( data )
+            },
+        }
+    }
+
+    /// Calls `f` on the data of an existing target node.
+    /// A new label is re-indexed in the parent and merged into the signatures, the old one is left behind there.
+    pub fn and_modify<F:FnOnce( &mut T )>( self, f: F ) -> Self {
+        match self {
+            Entry::Occupied( mut node ) => {
+                let node_ = unsafe{ node.as_mut().get_unchecked_mut() };
+                node_.update_data( f );
+                node_.link.merge_signature( Signature::of_label( node_.data.label() ));
+                Entry::Occupied( node )
+            },
+            Entry::Vacant( entry ) => Entry::Vacant( entry ),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::Entry;
+    use super::super::{tr, LtreePath};
+
+    fn path( s: &str ) -> LtreePath { s.parse().unwrap() }
+
+    #[test]
+    fn test_insert_path() {
+        let mut tree = tr( String::new() );
+        tree.insert_path( &path( "top.x.y" ), "y".to_owned() );
+        assert_eq!( tree.to_string(), "( top( x( y ) ) )" );
+        assert_eq!( tree.node_count(), 4 );
+
+        tree.insert_path( &path( "top.z" ), "z".to_owned() );
+        assert_eq!( tree.to_string(), "( top( x( y ) z ) )" );
+        assert_eq!( tree.node_count(), 5 );
+        assert_eq!( tree.get_path( &path( "top" )).unwrap().degree(), 2 );
+    }
+
+    #[test]
+    #[should_panic( expected = "last label of the path" )]
+    fn test_insert_path_checks_label() {
+        let mut tree = tr( String::new() );
+        tree.insert_path( &path( "top.x" ), "y".to_owned() );
+    }
+
+    #[test]
+    fn test_entry() {
+        let mut tree = tr( String::new() );
+        match tree.entry( &path( "top.x" )) {
+            Entry::Vacant( entry ) => assert_eq!( entry.labels(), &["top", "x"] ),
+            Entry::Occupied( _ ) => panic!( "top.x should be vacant" ),
+        }
+
+        tree.entry( &path( "top.x" )).or_create();
+        match tree.entry( &path( "top.x" )) {
+            Entry::Occupied( node ) => assert_eq!( node.data, "x" ),
+            Entry::Vacant( _ ) => panic!( "top.x should be occupied" ),
+        }
+
+        tree.entry( &path( "top.x" )).and_modify( |data| data.push( 'y' ));
+        assert!( tree.get_path( &path( "top.x" )).is_none() );
+        assert!( tree.get_path( &path( "top.xy" )).is_some() );
+    }
+}
@@ -1,5 +1,5 @@
 use super::rust::*;
-use super::{Node,Link,Tree,Iter,IterMut,OntoIter,Size};
+use super::{Node,Link,Tree,Iter,IterMut,OntoIter,Size,Signature,Walk};
 use super::bfs::{BfsForest,Splitted};
 
 pub struct Forest<T> {
@@ -51,6 +51,8 @@ impl<T> Forest<T> {
                 prev   : null_mut(),
                 parent : null_mut(),
                 size   ,
+                index  : None,
+                sig    : if child.is_null() { Signature::EMPTY } else { Signature::FULL },
             },
             mark : PhantomData
         };
@@ -109,8 +111,10 @@ impl<T> Forest<T> {
             tree.link_mut().set_sib( self.tail(), self.head() );
             self.link.adopt( tree_root, tree_root );
         }}
+        unsafe{ self.link.index_insert( tree_root ); }
         self.link.size.degree += 1;
         self.link.size.node_cnt += tree.root().size.node_cnt;
+        self.link.merge_signature( tree.root().link.sig );
         tree.clear();
     }
 
@@ -135,8 +139,10 @@ impl<T> Forest<T> {
             }
         }
         self.link.set_child( tree_root );
+        unsafe{ self.link.index_insert( tree_root ); }
         self.link.size.degree += 1;
         self.link.size.node_cnt += tree.root().size.node_cnt;
+        self.link.merge_signature( tree.root().link.sig );
         tree.clear();
     }
 
@@ -163,6 +169,7 @@ impl<T> Forest<T> {
                 (*self.new_head()).prev = self.tail();
                 (*self.tail()).next = self.new_head();
             }
+            self.link.index_remove( front );
             (*front).reset_parent();
             (*front).reset_sib();
             self.link.size.degree -= 1;
@@ -196,6 +203,7 @@ impl<T> Forest<T> {
                 (*self.head()).prev = new_tail;
                 self.link.set_child( new_tail );
             }
+            self.link.index_remove( back );
             (*back).reset_parent();
             (*back).reset_sib();
             self.link.size.degree -= 1;
@@ -218,6 +226,7 @@ impl<T> Forest<T> {
     /// ```
     #[inline] pub fn prepend( &mut self, mut forest: Forest<T> ) {
         if !forest.is_empty() {
+            unsafe{ self.link.index_insert_sibs( forest.head(), forest.tail() ); }
             if self.is_empty() {
                 self.link.set_child( forest.tail() );
             } else { unsafe {
@@ -226,6 +235,7 @@ impl<T> Forest<T> {
                 self.link.adopt( forest.tail(), forest_head );
             }}
             self.link.size += forest.size;
+            self.link.merge_signature( forest.link.sig );
             forest.clear();
         }
     }
@@ -244,6 +254,7 @@ impl<T> Forest<T> {
     /// ```
     #[inline] pub fn append( &mut self, mut forest: Forest<T> ) {
         if !forest.is_empty() {
+            unsafe{ self.link.index_insert_sibs( forest.head(), forest.tail() ); }
             if !self.is_empty() { unsafe {
                 let forest_head = forest.head();
                 forest.set_sib( self.tail(), self.head() );
@@ -251,6 +262,7 @@ impl<T> Forest<T> {
             }}
             self.link.set_child( forest.tail() );
             self.link.size += forest.size;
+            self.link.merge_signature( forest.link.sig );
             forest.clear();
         }
     }
@@ -263,6 +275,9 @@ impl<T> Forest<T> {
         }}
     }
 
+    /// Depth-first walk over the trees of the forest, see [`Walk`](walk/struct.Walk.html).
+    #[inline] pub fn walk( &self ) -> Walk<'_,T> { Walk::on_forest( self )}
+
     #[inline] pub fn iter_mut<'a>( &mut self ) -> IterMut<'a,T> {
         if self.is_empty() {
             IterMut::new( null_mut(), null_mut(), 0 )
@@ -431,6 +446,7 @@ impl<T:Ord> Ord for Forest<T> {
 
 impl<T:Hash> Hash for Forest<T> {
     fn hash<H:Hasher>( &self, state: &mut H ) {
+        self.degree().hash( state );
         for child in self.iter() {
             child.hash( state );
         }
@@ -1,4 +1,4 @@
-use super::{Node,Link,Size};
+use super::{Node,Link,Size,Signature};
 use super::rust::*;
 
 pub type Phantom<T> = PhantomData<Box<Node<T>>>;
@@ -12,6 +12,8 @@ pub(crate) fn make_node<T>( data: T ) -> *mut Node<T> {
                 prev   : null_mut(),
                 parent : null_mut(),
                 size   : Size{ degree: 0, node_cnt: 1 },
+                index  : None,
+                sig    : Signature::FULL,
             },
             data,
         }
@@ -21,6 +23,5 @@ pub(crate) fn make_node<T>( data: T ) -> *mut Node<T> {
 }
 
 pub(crate) fn drop_node<T>( node: *mut Node<T> ) {
-    println!("Drop Node {:?}", node);
-    unsafe{ Box::from_raw( node ); }
+    unsafe{ drop( Box::from_raw( node )); }
 }
@@ -0,0 +1,237 @@
+use super::{Node, Link, Label};
+use super::rust::*;
+use std::collections::HashMap;
+
+
+/// Degree from which path insertion starts indexing a node's children by label.
+///
+/// Only path insertion builds indexes by itself. Other operations adding children, such as
+/// `push_back`, `append`, `Subnode::insert_before` or `Subnode::lift`, keep an existing index
+/// up to date but do not create one: call `Node::build_index_if_wide` after them.
+pub const INDEX_THRESHOLD: usize = 16;
+
+
+/// Children of one node keyed by label, kept in the node's `Link`.
+///
+/// `Link` does not know the type of the data it carries, so the index keeps
+/// a monomorphized accessor which reads the label through the `Node<T>` behind a link.
+/// Labels are assumed to be unique among siblings, the first child wins otherwise.
+pub(crate) struct LabelIndex {
+    children : HashMap<String, *mut Link>,
+    label_of : unsafe fn( *const Link ) -> *const str,
+}
+
+unsafe fn label_of<T:Label>( link: *const Link ) -> *const str {
+    (*( link as *const Node<T> )).data.label() as *const str
+}
+
+impl LabelIndex {
+    pub(crate) fn new<T:Label>() -> Self {
+        LabelIndex{ children: HashMap::new(), label_of: label_of::<T> }
+    }
+
+    #[inline] pub(crate) fn get( &self, label: &str ) -> Option<*mut Link> { self.children.get( label ).cloned() }
+
+    #[inline] pub(crate) unsafe fn insert( &mut self, child: *mut Link ) {
+        let label = &*(self.label_of)( child );
+        self.children.entry( label.to_owned() ).or_insert( child );
+    }
+
+    #[inline] pub(crate) unsafe fn remove( &mut self, child: *mut Link ) {
+        let label = &*(self.label_of)( child );
+        if self.children.get( label ) == Some( &child ) {
+            self.children.remove( label );
+        }
+    }
+}
+
+impl Link {
+    #[inline] pub(crate) unsafe fn index_insert( &mut self, child: *mut Link ) {
+        if let Some( index ) = self.index.as_mut() {
+            index.insert( child );
+        }
+    }
+
+    #[inline] pub(crate) unsafe fn index_remove( &mut self, child: *mut Link ) {
+        if let Some( index ) = self.index.as_mut() {
+            index.remove( child );
+        }
+    }
+
+    /// Indexes the sibling list `head..=tail` which is about to be adopted.
+    pub(crate) unsafe fn index_insert_sibs( &mut self, head: *mut Link, tail: *mut Link ) {
+        if let Some( index ) = self.index.as_mut() {
+            let mut link = head;
+            loop {
+                index.insert( link );
+                if link == tail { break; }
+                link = (*link).next;
+            }
+        }
+    }
+}
+
+impl<T> Node<T> {
+    #[inline] pub fn has_index( &self ) -> bool { self.link.index.is_some() }
+
+    /// Drops the label index, child lookup falls back to scanning siblings.
+    #[inline] pub fn drop_index( &mut self ) { self.link.index = None; }
+}
+
+impl<T:Label> Node<T> {
+    /// Indexes children by label so that `child()` no longer scans siblings.
+    /// The index is kept up to date by every operation adding or removing children
+    /// and by `Entry::and_modify`, but not when the label of a child
+    /// is changed through `data` directly: call this again in that case.
+    pub fn build_index( &mut self ) {
+        let mut index = LabelIndex::new::<T>();
+        for child in self.iter_mut() {
+            unsafe{ index.insert( child.get_unchecked_mut().plink() ); }
+        }
+        self.link.index = Some( Box::new( index ));
+    }
+
+    /// Indexes children by label if there are at least `INDEX_THRESHOLD` of them and no index yet.
+    #[inline] pub fn build_index_if_wide( &mut self ) {
+        if !self.has_index() && self.degree() >= INDEX_THRESHOLD {
+            self.build_index();
+        }
+    }
+
+    /// Runs `f` on the data, re-indexing the node in its parent's index in case `f` changes the label.
+    pub(crate) fn update_data<R, F:FnOnce( &mut T ) -> R>( &mut self, f: F ) -> R {
+        let parent = self.link.parent;
+        unsafe {
+            if !parent.is_null() { (*parent).index_remove( self.plink() ); }
+            let result = f( &mut self.data );
+            if !parent.is_null() { (*parent).index_insert( self.plink() ); }
+            result
+        }
+    }
+
+    /// Builds the index of every node of the subtree with at least `INDEX_THRESHOLD` children,
+    /// as path insertion would have, e.g. for a subtree made by `to_owned` or `push_back`.
+    pub fn index_wide_nodes( &mut self ) {
+        let mut nodes = vec![ self as *mut Node<T> ];
+        while let Some( node ) = nodes.pop() {
+            unsafe {
+                (*node).build_index_if_wide();
+                nodes.extend( (*node).iter_mut().map( |child| child.get_unchecked_mut() as *mut Node<T> ));
+            }
+        }
+    }
+
+    /// Looks a child up through the index, `None` if the node has no index.
+    #[inline] pub(crate) fn indexed_child( &self, label: &str ) -> Option<Option<*mut Link>> {
+        self.link.index.as_ref().map( |index| index.get( label ))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::super::{tr, fr, LtreePath, INDEX_THRESHOLD};
+
+    fn path( s: &str ) -> LtreePath { s.parse().unwrap() }
+
+    fn indexed_len( node: &super::Node<String> ) -> usize {
+        node.link.index.as_ref().map_or( 0, |index| index.children.len() )
+    }
+
+    #[test]
+    fn test_index_built_on_wide_nodes() {
+        let mut tree = tr( String::new() );
+        for i in 0..INDEX_THRESHOLD+4 {
+            tree.insert_path( &path( &format!( "top.c{}", i )), format!( "c{}", i ));
+        }
+        let top = tree.get_path( &path( "top" )).unwrap();
+        assert!( top.has_index() );
+        assert!( !tree.has_index() );
+        assert_eq!( indexed_len( top ), INDEX_THRESHOLD+4 );
+        assert_eq!( top.child( "c7" ).unwrap().data, "c7" );
+        assert!( top.child( "c100" ).is_none() );
+    }
+
+    #[test]
+    fn test_index_follows_children() {
+        let mut tree = tr( String::new() ) /tr( "a".to_owned() ) /tr( "b".to_owned() );
+        let mut root = tree.root_mut();
+        let root = unsafe{ root.as_mut().get_unchecked_mut() };
+        root.build_index();
+        assert_eq!( indexed_len( root ), 2 );
+
+        root.push_front( tr( "c".to_owned() ));
+        root.append( -tr( "d".to_owned() ) -tr( "e".to_owned() ));
+        root.prepend( -tr( "f".to_owned() ));
+        assert_eq!( indexed_len( root ), 6 );
+        assert_eq!( root.child( "e" ).unwrap().data, "e" );
+
+        assert_eq!( root.pop_front().unwrap().data, "f" );
+        assert_eq!( root.pop_back().unwrap().data, "e" );
+        assert!( root.child( "f" ).is_none() );
+        assert!( root.child( "e" ).is_none() );
+
+        let departed = root.onto_iter().find( |sub| sub.data == "a" ).unwrap().depart();
+        assert_eq!( departed.data, "a" );
+        assert!( root.child( "a" ).is_none() );
+        for mut sub in root.onto_iter() {
+            if sub.data == "b" { sub.insert_after( tr( "g".to_owned() )); }
+        }
+        assert_eq!( indexed_len( root ), 4 );
+        assert_eq!( root.child( "g" ).unwrap().data, "g" );
+        assert_eq!( root.to_string(), "( c b g d )" );
+
+        root.append( fr() );
+        assert_eq!( indexed_len( root ), 4 );
+    }
+
+    #[test]
+    fn test_only_path_insertion_builds_index() {
+        let mut tree = tr( String::new() );
+        for i in 0..INDEX_THRESHOLD {
+            tree.root_mut().get_mut().push_back( tr( format!( "c{}", i )));
+        }
+        assert!( !tree.has_index() );
+        tree.root_mut().get_mut().build_index_if_wide();
+        assert!( tree.has_index() );
+
+        let mut tree = tr( String::new() ) /tr( "a".to_owned() );
+        for i in 0..INDEX_THRESHOLD {
+            tree.get_path_mut( &path( "a" )).unwrap().get_mut().push_back( tr( format!( "c{}", i )));
+        }
+        let root = tree.root_mut().get_mut();
+        root.onto_iter().next().unwrap().lift();
+        assert_eq!( root.degree(), INDEX_THRESHOLD );
+        assert!( !root.has_index() );
+        root.build_index_if_wide();
+        assert_eq!( indexed_len( root ), INDEX_THRESHOLD );
+    }
+
+    #[test]
+    fn test_index_wide_nodes() {
+        let mut tree = tr( String::new() );
+        for i in 0..INDEX_THRESHOLD {
+            tree.insert_path( &path( &format!( "top.c{}", i )), format!( "c{}", i ));
+        }
+        let mut copy = tree.get_path( &path( "top" )).unwrap().to_owned();
+        assert!( !copy.has_index() );
+        copy.root_mut().get_mut().index_wide_nodes();
+        assert!( copy.has_index() );
+        assert_eq!( indexed_len( &copy ), INDEX_THRESHOLD );
+        assert!( copy.iter().all( |child| !child.has_index() ));
+    }
+
+    #[test]
+    fn test_index_follows_relabel() {
+        let mut tree = tr( String::new() );
+        for i in 0..=INDEX_THRESHOLD {
+            tree.insert_path( &path( &format!( "c{}", i )), format!( "c{}", i ));
+        }
+        assert!( tree.has_index() );
+
+        tree.entry( &path( "c3" )).and_modify( |data| data.push( 'y' ));
+        assert!( tree.get_path( &path( "c3" )).is_none() );
+        assert_eq!( tree.get_path( &path( "c3y" )).unwrap().data, "c3y" );
+        assert_eq!( indexed_len( &tree ), INDEX_THRESHOLD+1 );
+    }
+}
@@ -19,8 +19,34 @@ mod bfs;
 mod onto_iter;
 pub use onto_iter::{Subnode, OntoIter};
 
+mod path;
+pub use path::{Label, LtreePath, PathError, MAX_LABEL_LEN, MAX_LEVELS};
+
+mod entry;
+pub use entry::{Entry, VacantEntry};
+
+mod index;
+pub use index::INDEX_THRESHOLD;
+
+pub mod lquery;
+pub use lquery::{Lquery, LqueryError};
+
+pub mod ltxtquery;
+pub use ltxtquery::{Ltxtquery, LtxtqueryError, MAX_NESTING};
+
+mod signature;
+pub use signature::Signature;
+
+pub mod walk;
+pub use walk::{Walk, TreeWalk, ForestWalk};
+
+mod traversal;
+pub use traversal::Traversal;
+
+mod paths;
+pub use paths::{DfsPaths, DfsDepths, BfsPaths, BfsDepths, BfsLimited};
+
 mod heap;
-mod walk;
 mod notation;
 pub use notation::{tr, fr};
 
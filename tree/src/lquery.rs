@@ -0,0 +1,516 @@
+use super::{Node, Label, LtreePath, MAX_LABEL_LEN, MAX_LEVELS};
+use super::path::is_label_char;
+use super::rust::*;
+use std::error::Error;
+use std::str::FromStr;
+
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LqueryError {
+    UnexpectedChar { position: usize, ch: char },
+    UnexpectedEnd,
+    /// A quantifier whose bounds are not numbers, exceed `MAX_LEVELS` or are reversed.
+    InvalidQuantifier { position: usize },
+    LabelTooLong { position: usize, len: usize },
+    TooManyLevels { levels: usize },
+}
+
+impl Display for LqueryError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            LqueryError::UnexpectedChar { position, ch } =>
+                write!(f, "lquery syntax error: unexpected character {:?} at position {}", ch, position),
+            LqueryError::UnexpectedEnd =>
+                write!(f, "lquery syntax error: unexpected end of input"),
+            LqueryError::InvalidQuantifier { position } =>
+                write!(f, "lquery syntax error: invalid quantifier at position {}", position),
+            LqueryError::LabelTooLong { position, len } =>
+                write!(f, "label at position {} is too long: {} bytes, maximum is {}", position, len, MAX_LABEL_LEN),
+            LqueryError::TooManyLevels { levels } =>
+                write!(f, "number of lquery levels ({}) exceeds the maximum allowed ({})", levels, MAX_LEVELS),
+        }
+    }
+}
+
+impl Error for LqueryError {}
+
+
+/// One alternative of a level, e.g. `sport*@`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Item {
+    pub label  : String,
+    /// `*`: the label only has to start with `label`.
+    pub prefix : bool,
+    /// `@`: compare case-insensitively.
+    pub icase  : bool,
+    /// `%`: compare `_` separated words instead of the whole label.
+    pub words  : bool,
+}
+
+impl Item {
+    pub fn matches(&self, label: &str) -> bool {
+        if self.words {
+            // every word of the pattern has to match one of the words of the label
+            self.label.split('_').filter(|word| !word.is_empty()).all(|pattern| {
+                label.split('_').filter(|word| !word.is_empty()).any(|word| self.matches_word(pattern, word))
+            })
+        } else {
+            self.matches_word(&self.label, label)
+        }
+    }
+
+    #[inline] fn matches_word(&self, pattern: &str, word: &str) -> bool {
+        if word.len() < pattern.len() || (!self.prefix && word.len() != pattern.len()) {
+            return false;
+        }
+        let word = &word.as_bytes()[..pattern.len()];
+        if self.icase {
+            word.eq_ignore_ascii_case(pattern.as_bytes())
+        } else {
+            word == pattern.as_bytes()
+        }
+    }
+}
+
+
+/// One `.` separated level of an lquery, matching between `min` and `max` labels.
+/// A level without items is `*` and matches any label.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Level {
+    pub items   : Vec<Item>,
+    /// `!`: the level matches labels which match none of the items.
+    pub negated : bool,
+    pub min     : u32,
+    pub max     : u32,
+}
+
+impl Level {
+    #[inline] pub fn is_star(&self) -> bool { self.items.is_empty() }
+
+    pub fn matches(&self, label: &str) -> bool {
+        self.is_star() || self.items.iter().any(|item| item.matches(label)) != self.negated
+    }
+
+    #[inline] fn is_unbounded(&self) -> bool { self.max as usize >= MAX_LEVELS }
+}
+
+
+/// Position of a partial match: how many labels the level at `level` has consumed so far.
+pub type MatchState = Vec<(usize, u32)>;
+
+
+/// A compiled lquery pattern, e.g. `Top.*{0,2}.sport*@.!football|tennis{1,}.Russ*|Spain`.
+///
+/// # Examples
+///
+/// ```
+/// use tree::{Lquery, LtreePath};
+/// let query: Lquery = "*.Astronomy.*".parse().unwrap();
+/// let path: LtreePath = "Top.Science.Astronomy.Cosmology".parse().unwrap();
+/// assert!( query.is_match( &path ));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lquery {
+    levels: Vec<Level>,
+}
+
+impl Lquery {
+    pub fn parse(s: &str) -> Result<Self, LqueryError> {
+        Parser { input: s.as_bytes(), pos: 0 }.parse()
+    }
+
+    #[inline] pub fn levels(&self) -> &[Level] { &self.levels }
+
+    pub fn is_match(&self, path: &LtreePath) -> bool {
+        self.matches(path.labels())
+    }
+
+    /// Whether the whole label sequence matches the pattern.
+    pub fn matches<S: AsRef<str>>(&self, labels: &[S]) -> bool {
+        let mut state = self.start();
+        for label in labels {
+            if state.is_empty() {
+                return false;
+            }
+            state = self.step(&state, label.as_ref());
+        }
+        self.accepts(&state)
+    }
+
+    /// State before any label has been consumed, i.e. for the empty path.
+    pub fn start(&self) -> MatchState {
+        let mut state = vec![(0, 0)];
+        self.close(&mut state);
+        state
+    }
+
+    /// Consumes one more label. An empty state can never match again,
+    /// so nothing below a node yielding it needs to be visited.
+    pub fn step(&self, state: &MatchState, label: &str) -> MatchState {
+        let mut next = MatchState::new();
+        for &(i, count) in state {
+            if let Some(level) = self.levels.get(i) {
+                if count < level.max && level.matches(label) {
+                    let count = if level.is_unbounded() { (count + 1).min(level.min) } else { count + 1 };
+                    next.push((i, count));
+                }
+            }
+        }
+        self.close(&mut next);
+        next
+    }
+
+    /// Whether the labels consumed so far form a match.
+    pub fn accepts(&self, state: &MatchState) -> bool {
+        state.iter().any(|&(i, _)| i == self.levels.len())
+    }
+
+    /// Adds every state reachable by ending levels which consumed enough labels.
+    fn close(&self, state: &mut MatchState) {
+        let mut i = 0;
+        while i < state.len() {
+            let (level, count) = state[i];
+            if let Some(lvl) = self.levels.get(level) {
+                if count >= lvl.min && !state.contains(&(level + 1, 0)) {
+                    state.push((level + 1, 0));
+                }
+            }
+            i += 1;
+        }
+        state.sort_unstable();
+        state.dedup();
+    }
+
+    /// Alternatives of which every matching path contains at least one,
+    /// taken from the levels which must match exact labels.
+    fn required(&self) -> Vec<Vec<&str>> {
+        self.levels.iter()
+            .filter(|level| level.min > 0 && !level.negated && !level.is_star())
+            .filter(|level| level.items.iter().all(|item| !item.prefix && !item.icase && !item.words))
+            .map(|level| level.items.iter().map(|item| item.label.as_str()).collect())
+            .collect()
+    }
+
+    /// Walks `node` depth first and returns the descendants whose path relative
+    /// to `node` matches, `node` itself being the empty path.
+    /// Subtrees no path can match below are not visited, nor those whose
+    /// `Signature` lacks a label the query requires.
+    pub fn search<'a, T: Label>(&self, node: &'a Node<T>) -> Vec<(LtreePath, &'a Node<T>)> {
+        Lquery::search_any(std::slice::from_ref(self), node)
+    }
+
+    /// Like `search`, for paths matching at least one of `queries`.
+    pub fn search_any<'a, T: Label>(queries: &[Lquery], node: &'a Node<T>) -> Vec<(LtreePath, &'a Node<T>)> {
+        let mut found = Vec::new();
+        let mut labels: Vec<String> = Vec::new();
+        let start = queries.iter().map(Lquery::start).collect::<Vec<_>>();
+        let required = queries.iter().map(Lquery::required).collect::<Vec<_>>();
+        let mut stack = vec![(node, 0usize, start)];
+
+        while let Some((node, depth, states)) = stack.pop() {
+            labels.truncate(depth.saturating_sub(1));
+            if depth > 0 {
+                labels.push(node.data.label().to_owned());
+            }
+            if queries.iter().zip(&states).any(|(query, state)| query.accepts(state)) {
+                found.push((LtreePath::from_labels(labels.clone()), node));
+            }
+
+            let mut children = Vec::with_capacity(node.degree());
+            for child in node.iter() {
+                let next = queries.iter().zip(&states)
+                    .map(|(query, state)| query.step(state, child.data.label()))
+                    .collect::<Vec<_>>();
+                let sig = child.signature();
+                let viable = next.iter().zip(&required).any(|(state, required)| {
+                    !state.is_empty() && required.iter().all(|alternatives| alternatives.iter().any(|label| {
+                        sig.may_contain(label) || labels.iter().any(|on_path| on_path == label)
+                    }))
+                });
+                if viable {
+                    children.push((child, depth + 1, next));
+                }
+            }
+            stack.extend(children.into_iter().rev());
+        }
+        found
+    }
+}
+
+impl FromStr for Lquery {
+    type Err = LqueryError;
+    fn from_str(s: &str) -> Result<Self, LqueryError> { Lquery::parse(s) }
+}
+
+impl Display for Lquery {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        for (i, level) in self.levels.iter().enumerate() {
+            if i > 0 {
+                f.write_str(".")?;
+            }
+            if level.is_star() {
+                f.write_str("*")?;
+            } else {
+                if level.negated {
+                    f.write_str("!")?;
+                }
+                for (j, item) in level.items.iter().enumerate() {
+                    if j > 0 {
+                        f.write_str("|")?;
+                    }
+                    f.write_str(&item.label)?;
+                    if item.prefix { f.write_str("*")?; }
+                    if item.icase { f.write_str("@")?; }
+                    if item.words { f.write_str("%")?; }
+                }
+            }
+            let (default_min, default_max) = if level.is_star() { (0, MAX_LEVELS as u32) } else { (1, 1) };
+            if (level.min, level.max) != (default_min, default_max) {
+                if level.min == level.max {
+                    write!(f, "{{{}}}", level.min)?;
+                } else if level.is_unbounded() {
+                    write!(f, "{{{},}}", level.min)?;
+                } else {
+                    write!(f, "{{{},{}}}", level.min, level.max)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+
+struct Parser<'s> {
+    input : &'s [u8],
+    pos   : usize,
+}
+
+impl<'s> Parser<'s> {
+    #[inline] fn peek(&self) -> Option<u8> { self.input.get(self.pos).cloned() }
+
+    fn unexpected(&self) -> LqueryError {
+        match std::str::from_utf8(&self.input[self.pos..]).ok().and_then(|rest| rest.chars().next()) {
+            Some(ch) => LqueryError::UnexpectedChar { position: self.pos, ch },
+            None if self.pos >= self.input.len() => LqueryError::UnexpectedEnd,
+            None => LqueryError::UnexpectedChar { position: self.pos, ch: char::REPLACEMENT_CHARACTER },
+        }
+    }
+
+    fn parse(mut self) -> Result<Lquery, LqueryError> {
+        let mut levels = Vec::new();
+        loop {
+            levels.push(self.level()?);
+            match self.peek() {
+                Some(b'.') => self.pos += 1,
+                None => break,
+                Some(_) => return Err(self.unexpected()),
+            }
+        }
+        if levels.len() > MAX_LEVELS {
+            return Err(LqueryError::TooManyLevels { levels: levels.len() });
+        }
+        Ok(Lquery { levels })
+    }
+
+    fn level(&mut self) -> Result<Level, LqueryError> {
+        let mut level = Level { items: Vec::new(), negated: false, min: 1, max: 1 };
+        match self.peek() {
+            Some(b'*') => {
+                self.pos += 1;
+                level.min = 0;
+                level.max = MAX_LEVELS as u32;
+            }
+            Some(b'!') => {
+                self.pos += 1;
+                level.negated = true;
+                self.items(&mut level)?;
+            }
+            _ => self.items(&mut level)?,
+        }
+        if self.peek() == Some(b'{') {
+            self.quantifier(&mut level)?;
+        }
+        Ok(level)
+    }
+
+    fn items(&mut self, level: &mut Level) -> Result<(), LqueryError> {
+        loop {
+            level.items.push(self.item()?);
+            if self.peek() == Some(b'|') {
+                self.pos += 1;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn item(&mut self) -> Result<Item, LqueryError> {
+        let start = self.pos;
+        while self.peek().is_some_and(|b| is_label_char(b as char)) {
+            self.pos += 1;
+        }
+        if self.pos == start {
+            return Err(self.unexpected());
+        }
+        if self.pos - start > MAX_LABEL_LEN {
+            return Err(LqueryError::LabelTooLong { position: start, len: self.pos - start });
+        }
+
+        let label = String::from_utf8(self.input[start..self.pos].to_vec()).expect("label chars are ascii");
+        let mut item = Item { label, prefix: false, icase: false, words: false };
+        loop {
+            match self.peek() {
+                Some(b'*') => item.prefix = true,
+                Some(b'@') => item.icase = true,
+                Some(b'%') => item.words = true,
+                _ => return Ok(item),
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn quantifier(&mut self, level: &mut Level) -> Result<(), LqueryError> {
+        let start = self.pos;
+        self.pos += 1;
+        let min = self.number(start)?;
+        let max = match self.peek() {
+            Some(b',') => {
+                self.pos += 1;
+                self.number(start)?
+            }
+            _ => Some(min.ok_or(LqueryError::InvalidQuantifier { position: start })?),
+        };
+        if self.peek() != Some(b'}') {
+            return Err(self.unexpected());
+        }
+        self.pos += 1;
+
+        let min = min.unwrap_or(0);
+        let max = max.unwrap_or(MAX_LEVELS as u32);
+        if min > max {
+            return Err(LqueryError::InvalidQuantifier { position: start });
+        }
+        level.min = min;
+        level.max = max;
+        Ok(())
+    }
+
+    fn number(&mut self, quantifier: usize) -> Result<Option<u32>, LqueryError> {
+        let start = self.pos;
+        while self.peek().is_some_and(|b| b.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        if self.pos == start {
+            return Ok(None);
+        }
+        std::str::from_utf8(&self.input[start..self.pos]).ok()
+            .and_then(|digits| digits.parse::<u32>().ok())
+            .filter(|&n| n as usize <= MAX_LEVELS)
+            .map(Some)
+            .ok_or(LqueryError::InvalidQuantifier { position: quantifier })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::tr;
+
+    fn is_match(query: &str, path: &str) -> bool {
+        query.parse::<Lquery>().unwrap().is_match(&path.parse().unwrap())
+    }
+
+    #[test]
+    fn test_plain_and_star() {
+        assert!(is_match("Top.Science", "Top.Science"));
+        assert!(!is_match("Top.Science", "Top.Science.Astronomy"));
+        assert!(is_match("*.Astronomy.*", "Top.Science.Astronomy"));
+        assert!(is_match("*.Astronomy.*", "Top.Science.Astronomy.Cosmology"));
+        assert!(!is_match("*.Astronomy.*", "Top.Science"));
+        assert!(is_match("*", ""));
+        assert!(is_match("*{2}", "a.b"));
+        assert!(!is_match("*{2}", "a.b.c"));
+        assert!(is_match("a.*{1,}", "a.b.c"));
+        assert!(!is_match("a.*{1,}", "a"));
+        assert!(is_match("a.*{,1}", "a"));
+        assert!(is_match("a{2,3}.b", "a.a.b"));
+        assert!(!is_match("a{2,3}.b", "a.b"));
+    }
+
+    #[test]
+    fn test_modifiers() {
+        assert!(!is_match("Top.sci*", "Top.Science"));
+        assert!(is_match("Top.sci*@", "Top.Science"));
+        assert!(is_match("Top.SCIENCE@", "Top.Science"));
+        assert!(is_match("foo_bar%", "foo_bar_baz"));
+        assert!(!is_match("foo_bar%", "foo_barbaz"));
+        assert!(is_match("foo_bar%*", "foo1_bar2_baz"));
+        assert!(is_match("a|b.c", "b.c"));
+        assert!(is_match("!a|b.c", "x.c"));
+        assert!(!is_match("!a|b.c", "a.c"));
+    }
+
+    #[test]
+    fn test_documented_example() {
+        let query = "Top.*{0,2}.sport*@.!football|tennis{1,}.Russ*|Spain";
+        assert!(is_match(query, "Top.Sports.Curling.Russia"));
+        assert!(is_match(query, "Top.a.b.sport.x.Spain"));
+        assert!(!is_match(query, "Top.a.b.c.sport.x.Spain"));
+        assert!(!is_match(query, "Top.sport.football.Spain"));
+        assert!(!is_match(query, "Top.sport.Spain"));
+        assert_eq!(query.parse::<Lquery>().unwrap().to_string(), query);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(Lquery::parse(""), Err(LqueryError::UnexpectedEnd));
+        assert_eq!(Lquery::parse("a..b"), Err(LqueryError::UnexpectedChar { position: 2, ch: '.' }));
+        assert_eq!(Lquery::parse("a b"), Err(LqueryError::UnexpectedChar { position: 1, ch: ' ' }));
+        assert_eq!(Lquery::parse("!*"), Err(LqueryError::UnexpectedChar { position: 1, ch: '*' }));
+        assert_eq!(Lquery::parse("*{3,1}"), Err(LqueryError::InvalidQuantifier { position: 1 }));
+        assert_eq!(Lquery::parse("*{}"), Err(LqueryError::InvalidQuantifier { position: 1 }));
+        assert_eq!(Lquery::parse("a{1"), Err(LqueryError::UnexpectedEnd));
+    }
+
+    #[test]
+    fn test_search() {
+        let mut tree = tr(String::new());
+        for path in &["Top.Science.Astronomy.Astrophysics", "Top.Science.Astronomy.Cosmology", "Top.Hobbies.Amateurs_Astronomy"] {
+            tree.insert_path(&path.parse().unwrap(), path.rsplit('.').next().unwrap().to_owned());
+        }
+        let query: Lquery = "*.Astronomy.*".parse().unwrap();
+        let found: Vec<String> = query.search(tree.root()).into_iter().map(|(path, _)| path.to_string()).collect();
+        assert_eq!(found, vec![
+            "Top.Science.Astronomy",
+            "Top.Science.Astronomy.Astrophysics",
+            "Top.Science.Astronomy.Cosmology",
+        ]);
+
+        let query: Lquery = "Top.Hobbies.*".parse().unwrap();
+        assert_eq!(query.search(tree.root()).len(), 2);
+
+        let queries = vec!["*.Cosmology".parse().unwrap(), "Top.Hobbies".parse().unwrap()];
+        let found: Vec<String> = Lquery::search_any(&queries, tree.root()).into_iter().map(|(path, _)| path.to_string()).collect();
+        assert_eq!(found, vec!["Top.Science.Astronomy.Cosmology", "Top.Hobbies"]);
+    }
+
+    #[test]
+    fn test_search_signatures() {
+        let mut tree = tr(String::new());
+        for path in &["Top.Science.Astronomy.Cosmology", "Top.Hobbies.Astronomy", "Top.Hobbies.Chess"] {
+            tree.insert_path(&path.parse().unwrap(), path.rsplit('.').next().unwrap().to_owned());
+        }
+        let hobbies = tree.get_path(&"Top.Hobbies".parse().unwrap()).unwrap();
+        assert!(hobbies.signature().may_contain("Chess"));
+
+        // `Top` is on the path when its children are pruned by signature
+        let query: Lquery = "Top.*.Astronomy|Chess".parse().unwrap();
+        let found: Vec<String> = query.search(tree.root()).into_iter().map(|(path, _)| path.to_string()).collect();
+        assert_eq!(found, vec!["Top.Science.Astronomy", "Top.Hobbies.Astronomy", "Top.Hobbies.Chess"]);
+
+        let query: Lquery = "*.Cosmology.*".parse().unwrap();
+        let found: Vec<String> = query.search(tree.root()).into_iter().map(|(path, _)| path.to_string()).collect();
+        assert_eq!(found, vec!["Top.Science.Astronomy.Cosmology"]);
+    }
+}
@@ -0,0 +1,336 @@
+use super::{Node, Label, LtreePath, MAX_LABEL_LEN};
+use super::lquery::Item;
+use super::path::is_label_char;
+use super::rust::*;
+use std::error::Error;
+use std::str::FromStr;
+
+
+/// Deepest nesting of `(` and `!` an ltxtquery may have, parsing and evaluation recursing once per level.
+pub const MAX_NESTING: usize = 64;
+
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LtxtqueryError {
+    UnexpectedChar { position: usize, ch: char },
+    UnexpectedEnd,
+    LabelTooLong { position: usize, len: usize },
+    TooDeep { position: usize },
+}
+
+impl Display for LtxtqueryError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            LtxtqueryError::UnexpectedChar { position, ch } =>
+                write!(f, "ltxtquery syntax error: unexpected character {:?} at position {}", ch, position),
+            LtxtqueryError::UnexpectedEnd =>
+                write!(f, "ltxtquery syntax error: unexpected end of input"),
+            LtxtqueryError::LabelTooLong { position, len } =>
+                write!(f, "label at position {} is too long: {} bytes, maximum is {}", position, len, MAX_LABEL_LEN),
+            LtxtqueryError::TooDeep { position } =>
+                write!(f, "ltxtquery nested deeper than {} levels at position {}", MAX_NESTING, position),
+        }
+    }
+}
+
+impl Error for LtxtqueryError {}
+
+
+/// Boolean expression over the words of an ltxtquery, words are indexes into `Ltxtquery::words`.
+///
+/// Chains of `&` and `|` are flat, so the expression is only as deep as its `(` and `!` nesting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Word(usize),
+    Not(Box<Expr>),
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+}
+
+impl Expr {
+    /// Evaluates the expression, `found[i]` telling whether word `i` matched a label.
+    pub fn eval(&self, found: &[bool]) -> bool {
+        match self {
+            Expr::Word(i) => found[*i],
+            Expr::Not(expr) => !expr.eval(found),
+            Expr::And(exprs) => exprs.iter().all(|expr| expr.eval(found)),
+            Expr::Or(exprs) => exprs.iter().any(|expr| expr.eval(found)),
+        }
+    }
+
+    /// Words every matching path contains a label equal to, so plain words outside `Not`.
+    fn required(&self, words: &[Item]) -> Vec<usize> {
+        match self {
+            Expr::Word(i) => {
+                let word = &words[*i];
+                if word.prefix || word.icase || word.words { Vec::new() } else { vec![*i] }
+            }
+            Expr::Not(_) => Vec::new(),
+            Expr::And(exprs) => exprs.iter().flat_map(|expr| expr.required(words)).collect(),
+            Expr::Or(exprs) => {
+                let (first, rest) = exprs.split_first().expect("an or has operands");
+                let rest = rest.iter().map(|expr| expr.required(words)).collect::<Vec<_>>();
+                first.required(words).into_iter().filter(|i| rest.iter().all(|required| required.contains(i))).collect()
+            }
+        }
+    }
+}
+
+
+/// A compiled ltxtquery, e.g. `Europe & Russia*@ & !Transportation`.
+///
+/// Words match labels anywhere in a path, with the same `*`, `@` and `%` modifiers as lquery;
+/// `!` binds tighter than `&`, which binds tighter than `|`.
+///
+/// # Examples
+///
+/// ```
+/// use tree::{Ltxtquery, LtreePath};
+/// let query: Ltxtquery = "Europe & Russia*@ & !Transportation".parse().unwrap();
+/// let path: LtreePath = "Top.Countries.Europe.Russia".parse().unwrap();
+/// assert!( query.is_match( &path ));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ltxtquery {
+    words : Vec<Item>,
+    expr  : Expr,
+}
+
+impl Ltxtquery {
+    pub fn parse(s: &str) -> Result<Self, LtxtqueryError> {
+        let mut parser = Parser { input: s.as_bytes(), pos: 0, words: Vec::new(), depth: 0 };
+        let expr = parser.or()?;
+        parser.skip_whitespace();
+        if parser.pos < parser.input.len() {
+            return Err(parser.unexpected());
+        }
+        Ok(Ltxtquery { words: parser.words, expr })
+    }
+
+    #[inline] pub fn words(&self) -> &[Item] { &self.words }
+    #[inline] pub fn expr(&self) -> &Expr { &self.expr }
+
+    pub fn is_match(&self, path: &LtreePath) -> bool {
+        self.matches(path.labels())
+    }
+
+    /// Whether the labels, taken as a set of words, satisfy the query.
+    pub fn matches<S: AsRef<str>>(&self, labels: &[S]) -> bool {
+        let mut found = vec![false; self.words.len()];
+        for label in labels {
+            self.mark(&mut found, label.as_ref());
+        }
+        self.expr.eval(&found)
+    }
+
+    /// Records which words `label` matches.
+    pub fn mark(&self, found: &mut [bool], label: &str) {
+        for (i, word) in self.words.iter().enumerate() {
+            if !found[i] && word.matches(label) {
+                found[i] = true;
+            }
+        }
+    }
+
+    /// Walks `node` depth first and returns the descendants whose path relative
+    /// to `node` matches, `node` itself being the empty path.
+    /// Subtrees whose `Signature` lacks a word the query requires are not visited.
+    pub fn search<'a, T: Label>(&self, node: &'a Node<T>) -> Vec<(LtreePath, &'a Node<T>)> {
+        let mut found = Vec::new();
+        let mut labels: Vec<String> = Vec::new();
+        let required = self.expr.required(&self.words);
+        let mut stack = vec![(node, 0usize, vec![false; self.words.len()])];
+
+        while let Some((node, depth, mut words)) = stack.pop() {
+            labels.truncate(depth.saturating_sub(1));
+            if depth > 0 {
+                labels.push(node.data.label().to_owned());
+                self.mark(&mut words, node.data.label());
+            }
+            if self.expr.eval(&words) {
+                found.push((LtreePath::from_labels(labels.clone()), node));
+            }
+            let children = node.iter()
+                .filter(|child| required.iter().all(|&i| words[i] || child.signature().may_contain(&self.words[i].label)))
+                .map(|child| (child, depth + 1, words.clone()))
+                .collect::<Vec<_>>();
+            stack.extend(children.into_iter().rev());
+        }
+        found
+    }
+}
+
+impl FromStr for Ltxtquery {
+    type Err = LtxtqueryError;
+    fn from_str(s: &str) -> Result<Self, LtxtqueryError> { Ltxtquery::parse(s) }
+}
+
+
+struct Parser<'s> {
+    input : &'s [u8],
+    pos   : usize,
+    words : Vec<Item>,
+    depth : usize, // `(` and `!` levels around the current position
+}
+
+impl<'s> Parser<'s> {
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_whitespace();
+        self.input.get(self.pos).cloned()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.input.get(self.pos).is_some_and(|b| b.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn unexpected(&self) -> LtxtqueryError {
+        match std::str::from_utf8(&self.input[self.pos..]).ok().and_then(|rest| rest.chars().next()) {
+            Some(ch) => LtxtqueryError::UnexpectedChar { position: self.pos, ch },
+            None if self.pos >= self.input.len() => LtxtqueryError::UnexpectedEnd,
+            None => LtxtqueryError::UnexpectedChar { position: self.pos, ch: char::REPLACEMENT_CHARACTER },
+        }
+    }
+
+    // or := and ('|' and)*
+    fn or(&mut self) -> Result<Expr, LtxtqueryError> {
+        let mut exprs = vec![self.and()?];
+        while self.peek() == Some(b'|') {
+            self.pos += 1;
+            exprs.push(self.and()?);
+        }
+        Ok(if exprs.len() == 1 { exprs.pop().expect("one operand") } else { Expr::Or(exprs) })
+    }
+
+    // and := not ('&' not)*
+    fn and(&mut self) -> Result<Expr, LtxtqueryError> {
+        let mut exprs = vec![self.not()?];
+        while self.peek() == Some(b'&') {
+            self.pos += 1;
+            exprs.push(self.not()?);
+        }
+        Ok(if exprs.len() == 1 { exprs.pop().expect("one operand") } else { Expr::And(exprs) })
+    }
+
+    // not := '!' not | '(' or ')' | word
+    fn not(&mut self) -> Result<Expr, LtxtqueryError> {
+        match self.peek() {
+            Some(b'!') => {
+                self.nest()?;
+                let expr = Expr::Not(Box::new(self.not()?));
+                self.depth -= 1;
+                Ok(expr)
+            }
+            Some(b'(') => {
+                self.nest()?;
+                let expr = self.or()?;
+                if self.peek() != Some(b')') {
+                    return Err(self.unexpected());
+                }
+                self.pos += 1;
+                self.depth -= 1;
+                Ok(expr)
+            }
+            _ => self.word(),
+        }
+    }
+
+    /// Enters the `(` or `!` at the current position.
+    fn nest(&mut self) -> Result<(), LtxtqueryError> {
+        if self.depth >= MAX_NESTING {
+            return Err(LtxtqueryError::TooDeep { position: self.pos });
+        }
+        self.depth += 1;
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn word(&mut self) -> Result<Expr, LtxtqueryError> {
+        let start = self.pos;
+        while self.input.get(self.pos).is_some_and(|&b| is_label_char(b as char)) {
+            self.pos += 1;
+        }
+        if self.pos == start {
+            return Err(self.unexpected());
+        }
+        if self.pos - start > MAX_LABEL_LEN {
+            return Err(LtxtqueryError::LabelTooLong { position: start, len: self.pos - start });
+        }
+
+        let label = String::from_utf8(self.input[start..self.pos].to_vec()).expect("label chars are ascii");
+        let mut item = Item { label, prefix: false, icase: false, words: false };
+        loop {
+            match self.input.get(self.pos) {
+                Some(b'*') => item.prefix = true,
+                Some(b'@') => item.icase = true,
+                Some(b'%') => item.words = true,
+                _ => break,
+            }
+            self.pos += 1;
+        }
+        self.words.push(item);
+        Ok(Expr::Word(self.words.len() - 1))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::tr;
+
+    fn is_match(query: &str, path: &str) -> bool {
+        query.parse::<Ltxtquery>().unwrap().is_match(&path.parse().unwrap())
+    }
+
+    #[test]
+    fn test_match() {
+        let query = "Europe & Russia*@ & !Transportation";
+        assert!(is_match(query, "Top.Countries.Europe.Russia"));
+        assert!(is_match(query, "Europe.RUSSIAN_federation"));
+        assert!(!is_match(query, "Top.Countries.Europe.Russia.Transportation"));
+        assert!(!is_match(query, "Top.Countries.Asia.Russia"));
+
+        assert!(is_match("a | b & c", "a"));
+        assert!(!is_match("(a | b) & c", "a"));
+        assert!(is_match("!(a | b)", "c.d"));
+        assert!(is_match("!!a", "a"));
+        assert!(is_match("Amateurs%", "Top.Hobbies.Amateurs_Astronomy"));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(Ltxtquery::parse(""), Err(LtxtqueryError::UnexpectedEnd));
+        assert_eq!(Ltxtquery::parse("a &"), Err(LtxtqueryError::UnexpectedEnd));
+        assert_eq!(Ltxtquery::parse("(a | b"), Err(LtxtqueryError::UnexpectedEnd));
+        assert_eq!(Ltxtquery::parse("a b"), Err(LtxtqueryError::UnexpectedChar { position: 2, ch: 'b' }));
+        assert_eq!(Ltxtquery::parse("a & .b"), Err(LtxtqueryError::UnexpectedChar { position: 4, ch: '.' }));
+
+        let deep = format!("{}a{}", "(".repeat(MAX_NESTING + 1), ")".repeat(MAX_NESTING + 1));
+        assert_eq!(Ltxtquery::parse(&deep), Err(LtxtqueryError::TooDeep { position: MAX_NESTING }));
+        assert_eq!(Ltxtquery::parse(&"!".repeat(100_000)), Err(LtxtqueryError::TooDeep { position: MAX_NESTING }));
+        assert!(Ltxtquery::parse(&format!("{}a", "!".repeat(MAX_NESTING))).is_ok());
+
+        let chain = vec!["a"; 100_000].join(" & ");
+        let query = Ltxtquery::parse(&chain).unwrap();
+        assert!(matches!(query.expr(), Expr::And(exprs) if exprs.len() == 100_000));
+        assert!(query.is_match(&"a".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_search() {
+        let mut tree = tr(String::new());
+        for path in &["Top.Science.Astronomy.Cosmology", "Top.Hobbies.Amateurs_Astronomy"] {
+            tree.insert_path(&path.parse().unwrap(), path.rsplit('.').next().unwrap().to_owned());
+        }
+        let query: Ltxtquery = "Astronomy% & !Science".parse().unwrap();
+        let found: Vec<String> = query.search(tree.root()).into_iter().map(|(path, _)| path.to_string()).collect();
+        assert_eq!(found, vec!["Top.Hobbies.Amateurs_Astronomy"]);
+
+        let query: Ltxtquery = "Top & (Cosmology | Hobbies & !Chess)".parse().unwrap();
+        assert_eq!(query.expr().required(query.words()), vec![0]);
+        let found: Vec<String> = query.search(tree.root()).into_iter().map(|(path, _)| path.to_string()).collect();
+        assert_eq!(found, vec!["Top.Science.Astronomy.Cosmology", "Top.Hobbies", "Top.Hobbies.Amateurs_Astronomy"]);
+    }
+}
@@ -1,6 +1,9 @@
 use super::rust::*;
 use super::bfs::{BfsTree, Splitted, Split};
-use super::{Tree,Forest,Iter,IterMut,OntoIter,Size};
+use super::{Tree,Forest,Iter,IterMut,OntoIter,Size,Walk};
+use super::{Label,LtreePath,Entry,VacantEntry};
+use super::index::LabelIndex;
+use super::Signature;
 
 
 pub struct Link {
@@ -9,6 +12,8 @@ pub struct Link {
     pub(crate) prev   : *mut Link, // previous sibling
     pub(crate) parent : *mut Link,
     pub(crate) size   : Size,
+    pub(crate) index  : Option<Box<LabelIndex>>, // children by label, see `Node::build_index`
+    pub(crate) sig    : Signature, // labels in the subtree, see `Signature`
 }
 
 #[repr(C)]
@@ -147,8 +152,10 @@ impl<T> Node<T> {
                 tree.link_mut().set_sib(self.tail(), self.head());
                 self.link.adopt(tree_root, tree_root)
             }
+            self.link.index_insert(tree_root);
         }
         self.link.inc_sizes(1, tree.root().size.node_cnt);
+        self.link.merge_signature(tree.root().link.sig);
         tree.clear()
     }
 
@@ -165,6 +172,7 @@ impl<T> Node<T> {
                     (*self.tail()).next = self.new_head();
                 }
 
+                self.link.index_remove(front);
                 (*front).reset_parent();
                 (*front).reset_sib();
                 self.link.dec_sizes( 1, (*front).size.node_cnt );
@@ -182,9 +190,11 @@ impl<T> Node<T> {
                 self.link.adopt(tree_root, tree_root);
             }
             self.link.set_child(tree_root);
+            self.link.index_insert(tree_root);
         }
 
         self.link.inc_sizes(1, tree.root().size.node_cnt);
+        self.link.merge_signature(tree.root().link.sig);
         tree.clear();
     }
 
@@ -203,6 +213,7 @@ impl<T> Node<T> {
                     self.link.set_child(new_tail);
                 }
 
+                self.link.index_remove(back);
                 (*back).reset_parent();
                 (*back).reset_sib();
                 self.link.dec_sizes(1, (*back).size.node_cnt);
@@ -213,6 +224,7 @@ impl<T> Node<T> {
 
     pub fn prepend(&mut self, mut forest: Forest<T>) {
         if !forest.is_empty() {
+            unsafe { self.link.index_insert_sibs(forest.head(), forest.tail()); }
             forest.set_parent(self.plink());
             if self.is_leaf() {
                 self.link.set_child(forest.tail());
@@ -224,12 +236,14 @@ impl<T> Node<T> {
                 }
             }
             self.link.inc_sizes(forest.size.degree, forest.size.node_cnt);
+            self.link.merge_signature(forest.link.sig);
             forest.clear();
         }
     }
 
     pub fn append(&mut self, mut forest: Forest<T>) {
         if !forest.is_empty() {
+            unsafe { self.link.index_insert_sibs(forest.head(), forest.tail()); }
             forest.set_parent(self.plink());
             if self.is_leaf() {
                self.link.set_child(forest.tail());
@@ -241,6 +255,7 @@ impl<T> Node<T> {
             }}
 
             self.link.inc_sizes(forest.size.degree, forest.size.node_cnt);
+            self.link.merge_signature(forest.link.sig);
             forest.clear();
         }
     }
@@ -256,6 +271,9 @@ impl<T> Node<T> {
         }
     }
 
+    /// Depth-first walk over this node and its descendants, see [`Walk`](walk/struct.Walk.html).
+    pub fn walk(&self) -> Walk<'_, T> { Walk::on_node(self) }
+
     pub fn iter_mut<'a, 's:'a>(&'s mut self) -> IterMut<'a, T> {
         if self.is_leaf() {
             IterMut::new(null_mut(), null_mut(), 0)
@@ -291,6 +309,8 @@ impl<T> Node<T> {
         }
     }
 
+    /// Every node below `self` in breadth-first order, see `bfs_limited`, `bfs_depths` and `bfs_paths`
+    /// for traversals bounded by depth or by a predicate.
     pub fn bfs( &self) -> BfsTree<Splitted<Iter<T>>> {
         BfsTree::from(self, Size{
             degree:1,
@@ -311,6 +331,86 @@ impl<T> Node<T> {
 }
 
 
+impl<T:Label> Node<T> {
+    /// Returns the child whose data has the given label,
+    /// through the label index if the node has one.
+    pub fn child( &self, label: &str ) -> Option<&Node<T>> {
+        match self.indexed_child( label ) {
+            Some( found ) => found.map( |link| unsafe{ &*( link as *const Node<T> )}),
+            None => self.iter().find( |child| child.data.label() == label ),
+        }
+    }
+
+    pub fn child_mut( &mut self, label: &str ) -> Option<Pin<&mut Node<T>>> {
+        match self.indexed_child( label ) {
+            Some( found ) => found.map( |link| unsafe{ Pin::new_unchecked( &mut *( link as *mut Node<T> ))}),
+            None => self.iter_mut().find( |child| child.data.label() == label ),
+        }
+    }
+
+    /// Returns the descendant addressed by `path`, relative to this node.
+    /// The empty path addresses the node itself.
+    pub fn get_path( &self, path: &LtreePath ) -> Option<&Node<T>> {
+        let mut node = self;
+        for label in path {
+            node = node.child( label )?;
+        }
+        Some( node )
+    }
+
+    pub fn get_path_mut( &mut self, path: &LtreePath ) -> Option<Pin<&mut Node<T>>> {
+        match self.entry( path ) {
+            Entry::Occupied( node ) => Some( node ),
+            Entry::Vacant( _ ) => None,
+        }
+    }
+
+    /// Gets the entry of the descendant addressed by `path` for in-place manipulation.
+    pub fn entry( &mut self, path: &LtreePath ) -> Entry<'_,T> {
+        let mut node = self as *mut Node<T>;
+        for ( depth, label ) in path.iter().enumerate() {
+            match unsafe{ (*node).child_mut( label )} {
+                Some( child ) => node = unsafe{ child.get_unchecked_mut() },
+                None => return Entry::Vacant( VacantEntry::new(
+                    unsafe{ Pin::new_unchecked( &mut *node )},
+                    path.labels()[depth..].to_vec(),
+                )),
+            }
+        }
+        Entry::Occupied( unsafe{ Pin::new_unchecked( &mut *node )})
+    }
+
+    /// Inserts `data` at `path`, creating any missing intermediate node with `Label::from_label`.
+    /// Data of an existing node at `path` is replaced.
+    ///
+    /// # Panics
+    ///
+    /// If `data` is not labeled with the last label of a non empty `path`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tree::{tr, LtreePath};
+    /// let mut tree = tr( String::new() );
+    /// let path: LtreePath = "top.x".parse().unwrap();
+    /// tree.insert_path( &path, "x".to_owned() );
+    /// assert_eq!( tree.to_string(), "( top( x ) )" );
+    /// ```
+    pub fn insert_path( &mut self, path: &LtreePath, data: T ) -> Pin<&mut Node<T>> {
+        if let Some( label ) = path.last() {
+            assert_eq!( data.label(), label, "data must be labeled with the last label of the path" );
+        }
+        match self.entry( path ) {
+            Entry::Occupied( mut node ) => {
+                unsafe{ node.as_mut().get_unchecked_mut().data = data; }
+                node
+            },
+            Entry::Vacant( entry ) => entry.insert( data ),
+        }
+    }
+}
+
+
 impl<'a, T:'a> Split for &'a Node<T> {
     type Item = &'a T;
     type Iter = Iter<'a, T>;
@@ -363,6 +463,7 @@ impl<T:Clone> ToOwned for Node<T> {
         for child in self.iter() {
             tree.root_mut_().push_back( child.to_owned() );
         }
+        tree.link_mut().sig = self.link.sig;
         tree
     }
 }
@@ -450,8 +551,11 @@ impl<T:Ord> Ord for Node<T> {
 }
 
 impl<T:Hash> Hash for Node<T> {
+    /// Hashes the data and degree of every node in pre-order,
+    /// so that different shapes over the same data hash differently.
     fn hash<H:Hasher>( &self, state: &mut H ) {
         self.data.hash( state );
+        self.degree().hash( state );
         for child in self.iter() {
             child.hash( state );
         }
@@ -459,3 +563,25 @@ impl<T:Hash> Hash for Node<T> {
 }
 
 
+
+
+#[cfg(test)]
+mod tests {
+    use super::super::tr;
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    fn hash<T: Hash>( value: &T ) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash( &mut hasher );
+        hasher.finish()
+    }
+
+    #[test]
+    fn test_hash_structure() {
+        let chain = tr(0) /( tr(1) /tr(2) );
+        let siblings = tr(0) /tr(1) /tr(2);
+        assert_ne!( hash( &chain ), hash( &siblings ));
+        assert_eq!( hash( &chain ), hash( &( tr(0) /( tr(1) /tr(2) ))));
+    }
+}
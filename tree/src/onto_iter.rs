@@ -1,4 +1,4 @@
-use super::{Node,Link,Tree,Size};
+use super::{Node,Link,Tree,Label};
 use super::rust::*;
 
 
@@ -27,7 +27,9 @@ impl<'a, T:'a> Subnode<'a,T> {
             sib.link_mut().set_sib( self.node.prev, self.node.plink() );
             self.node.link.prev = sib.root_mut_().plink();
             sib.link_mut().set_parent( self.node.parent );
-            (*self.parent).size += Size{ degree: 1, node_cnt: sib.root().size.node_cnt };
+            (*self.parent).inc_sizes( 1, sib.root().size.node_cnt );
+            (*self.parent).index_insert( sib.root_mut_().plink() );
+            (*self.parent).merge_signature( sib.root().link.sig );
         }
         sib.clear();
     }
@@ -50,7 +52,9 @@ impl<'a, T:'a> Subnode<'a,T> {
             self.node.link.next = sib.root_mut_().plink();
             let parent = self.node.parent;
             sib.link_mut().set_parent( parent );
-            (*self.parent).size += Size{ degree: 1, node_cnt: sib.root().size.node_cnt };
+            (*self.parent).inc_sizes( 1, sib.root().size.node_cnt );
+            (*self.parent).index_insert( sib.root_mut_().plink() );
+            (*self.parent).merge_signature( sib.root().link.sig );
             if (*parent).tail() == self.node.plink() {
                 (*parent).set_child( sib.root_mut_().plink() );
             }
@@ -74,13 +78,53 @@ impl<'a, T:'a> Subnode<'a,T> {
             if (*self.parent).tail() == self.node.plink() {
                 (*self.parent).set_child( if self.node.has_no_sib() { null_mut() } else { self.node.prev });
             }
-            (*self.parent).size -= Size{ degree: 1, node_cnt: self.node.size.node_cnt };
+            (*self.parent).dec_sizes( 1, self.node.size.node_cnt );
+            (*self.parent).index_remove( self.node.plink() );
             self.node.link.reset_parent();
             (*self.node.prev).next = self.node.next;
             (*self.node.next).prev = self.node.prev;
             Tree::from( self.node.plink() )
         }
     }
+
+    /// Replaces `self` with its children, kept in order, and returns the now childless node as a `Tree`.
+    ///
+    /// A child may share the label of `self`: it is indexed again once `self` has left the parent.
+    #[inline] pub fn lift( mut self ) -> Tree<T> {
+        let parent = self.parent;
+        let mut lifted = Vec::with_capacity( self.node.degree() );
+        while let Some( child ) = self.node.pop_front() {
+            lifted.push( child.root as *mut Link );
+            self.insert_before( child );
+        }
+        let tree = self.depart();
+        for child in lifted {
+            unsafe{ (*parent).index_insert( child ); }
+        }
+        tree
+    }
+
+    /// Puts `tree` in the place of `self`, and returns `self` as a `Tree`.
+    ///
+    /// `tree` may share the label of `self`: it is indexed once `self` has left the parent.
+    #[inline] pub fn replace( mut self, mut tree: Tree<T> ) -> Tree<T> {
+        let parent = self.parent;
+        let root = tree.root_mut_().plink();
+        self.insert_before( tree );
+        let replaced = self.depart();
+        unsafe{ (*parent).index_insert( root ); }
+        replaced
+    }
+}
+
+impl<T:Label> Node<T> {
+    /// The child labeled `label`, found like [`child`](struct.Node.html#method.child),
+    /// as a `Subnode` to insert siblings next to or to depart without scanning the children.
+    pub fn child_subnode( &mut self, label: &str ) -> Option<Subnode<'_,T>> {
+        let parent = self.plink();
+        let child = self.child_mut( label )?;
+        Some( Subnode{ node: unsafe{ child.get_unchecked_mut() }, parent })
+    }
 }
 
 impl<'a, T:'a> Deref for Subnode<'a,T> {
@@ -130,3 +174,54 @@ impl<'a, T> ExactSizeIterator for OntoIter<'a, T> {}
 impl<'a, T> FusedIterator for OntoIter<'a, T> {}
 
 
+
+
+#[cfg(test)]
+mod tests {
+    use super::super::{tr, INDEX_THRESHOLD};
+
+    #[test]
+    fn test_lift() {
+        let mut tree = tr(0) /tr(1) /( tr(2) /tr(3) /tr(4) ) /tr(5);
+        let lifted = tree.root_mut().get_mut().onto_iter().nth(1).unwrap().lift();
+        assert_eq!( lifted.to_string(), "2" );
+        assert_eq!( tree.to_string(), "0( 1 3 4 5 )" );
+        assert_eq!( tree.degree(), 4 );
+        assert_eq!( tree.node_count(), 5 );
+    }
+
+    #[test]
+    fn test_lift_indexed() {
+        let mut tree = tr( String::new() );
+        for i in 0..=INDEX_THRESHOLD {
+            tree.insert_path( &format!( "c{}", i ).parse().unwrap(), format!( "c{}", i ));
+        }
+        tree.insert_path( &"c0.c0".parse().unwrap(), "c0".to_owned() );
+        assert!( tree.root().has_index() );
+
+        let lifted = tree.root_mut().get_mut().onto_iter().next().unwrap().lift();
+        assert_eq!( lifted.degree(), 0 );
+        assert_eq!( tree.degree(), INDEX_THRESHOLD + 1 );
+        assert!( tree.get_path( &"c0".parse().unwrap() ).is_some() );
+        assert!( tree.get_path( &"c16".parse().unwrap() ).is_some() );
+    }
+
+    #[test]
+    fn test_child_subnode() {
+        let mut tree = tr( String::new() );
+        for i in 0..=INDEX_THRESHOLD {
+            tree.insert_path( &format!( "c{}", i ).parse().unwrap(), format!( "c{}", i ));
+        }
+        let root = tree.root_mut().get_mut();
+        assert!( root.child_subnode( "missing" ).is_none() );
+        root.child_subnode( "c3" ).unwrap().insert_after( tr( "d".to_owned() ));
+        let replaced = root.child_subnode( "c5" ).unwrap().replace( tr( "c5".to_owned() ) /tr( "e".to_owned() ));
+        assert_eq!( replaced.degree(), 0 );
+        assert_eq!( root.child( "c5" ).unwrap().degree(), 1 );
+        assert_eq!( root.child_subnode( "c7" ).unwrap().depart().data, "c7" );
+        assert!( root.child( "c7" ).is_none() );
+        let labels = root.iter().take( 7 ).map( |child| child.data.as_str() ).collect::<Vec<_>>();
+        assert_eq!( labels, vec![ "c0", "c1", "c2", "c3", "d", "c4", "c5" ]);
+        assert_eq!( root.degree(), INDEX_THRESHOLD + 1 );
+    }
+}
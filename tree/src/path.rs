@@ -0,0 +1,350 @@
+use super::rust::*;
+use std::error::Error;
+use std::str::FromStr;
+
+
+/// Maximum length in bytes of a single label, same as Postgres ltree.
+pub const MAX_LABEL_LEN: usize = 1000;
+
+/// Maximum number of labels in a path, same as Postgres ltree.
+pub const MAX_LEVELS: usize = 65535;
+
+pub const SEPARATOR: char = '.';
+
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathError {
+    /// Two separators in a row, or a leading/trailing separator.
+    EmptyLabel { position: usize },
+    /// A character outside `[A-Za-z0-9_-]`.
+    InvalidChar { position: usize, ch: char },
+    LabelTooLong { position: usize, len: usize },
+    TooManyLevels { levels: usize },
+    /// Positions out of the path given to `subltree` or `subpath`.
+    InvalidPositions { start: isize, end: isize },
+}
+
+impl Display for PathError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            PathError::EmptyLabel { position } =>
+                write!(f, "ltree syntax error: empty label at position {}", position),
+            PathError::InvalidChar { position, ch } =>
+                write!(f, "ltree syntax error: unexpected character {:?} at position {}", ch, position),
+            PathError::LabelTooLong { position, len } =>
+                write!(f, "label at position {} is too long: {} bytes, maximum is {}", position, len, MAX_LABEL_LEN),
+            PathError::TooManyLevels { levels } =>
+                write!(f, "number of ltree levels ({}) exceeds the maximum allowed ({})", levels, MAX_LEVELS),
+            PathError::InvalidPositions { start, end } =>
+                write!(f, "invalid positions: {} to {}", start, end),
+        }
+    }
+}
+
+impl Error for PathError {}
+
+
+#[inline]
+pub fn is_label_char(ch: char) -> bool {
+    ch.is_ascii_alphanumeric() || ch == '_' || ch == '-'
+}
+
+/// Checks one label, `offset` is where the label starts in the original input
+/// and is only used to report errors.
+pub fn validate_label(label: &str, offset: usize) -> Result<(), PathError> {
+    if label.is_empty() {
+        return Err(PathError::EmptyLabel { position: offset });
+    }
+    if let Some((i, ch)) = label.char_indices().find(|&(_, ch)| !is_label_char(ch)) {
+        return Err(PathError::InvalidChar { position: offset + i, ch });
+    }
+    if label.len() > MAX_LABEL_LEN {
+        return Err(PathError::LabelTooLong { position: offset, len: label.len() });
+    }
+    Ok(())
+}
+
+
+/// Data that knows the label its node is addressed by.
+pub trait Label {
+    fn label(&self) -> &str;
+
+    /// Data for a node created only to complete a path,
+    /// e.g. `top` when `top.x` is inserted into an empty tree.
+    fn from_label(label: &str) -> Self;
+}
+
+impl Label for String {
+    #[inline] fn label(&self) -> &str { self }
+    #[inline] fn from_label(label: &str) -> Self { label.to_owned() }
+}
+
+
+/// A validated label path such as `Top.Science.Astronomy`.
+///
+/// The empty path has no labels and addresses the root of a tree.
+///
+/// # Examples
+///
+/// ```
+/// use tree::LtreePath;
+/// let path: LtreePath = "Top.Science".parse().unwrap();
+/// assert_eq!( path.nlevel(), 2 );
+/// assert_eq!( path.to_string(), "Top.Science" );
+/// assert!( "Top..Science".parse::<LtreePath>().is_err() );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LtreePath {
+    labels: Vec<String>,
+}
+
+impl LtreePath {
+    #[inline] pub fn new() -> Self { LtreePath { labels: Vec::new() } }
+
+    pub fn parse(s: &str) -> Result<Self, PathError> {
+        let mut path = LtreePath::new();
+        if s.is_empty() {
+            return Ok(path);
+        }
+
+        let mut offset = 0;
+        for label in s.split(SEPARATOR) {
+            validate_label(label, offset)?;
+            path.labels.push(label.to_owned());
+            offset += label.len() + 1;
+        }
+        if path.labels.len() > MAX_LEVELS {
+            return Err(PathError::TooManyLevels { levels: path.labels.len() });
+        }
+        Ok(path)
+    }
+
+    /// Builds a path from labels already known to be valid, e.g. read back from a tree.
+    #[inline] pub(crate) fn from_labels(labels: Vec<String>) -> Self { LtreePath { labels } }
+
+    #[inline] pub fn labels(&self) -> &[String] { &self.labels }
+    #[inline] pub fn iter(&self) -> std::slice::Iter<'_, String> { self.labels.iter() }
+
+    /// Number of labels in the path.
+    #[inline] pub fn nlevel(&self) -> usize { self.labels.len() }
+    #[inline] pub fn len(&self) -> usize { self.labels.len() }
+    #[inline] pub fn is_empty(&self) -> bool { self.labels.is_empty() }
+
+    #[inline] pub fn first(&self) -> Option<&str> { self.labels.first().map(String::as_str) }
+    #[inline] pub fn last(&self) -> Option<&str> { self.labels.last().map(String::as_str) }
+
+    pub fn push(&mut self, label: &str) -> Result<(), PathError> {
+        validate_label(label, 0)?;
+        if self.labels.len() >= MAX_LEVELS {
+            return Err(PathError::TooManyLevels { levels: self.labels.len() + 1 });
+        }
+        self.labels.push(label.to_owned());
+        Ok(())
+    }
+
+    #[inline] pub fn pop(&mut self) -> Option<String> { self.labels.pop() }
+
+    /// The path without its last label, or `None` for the empty path.
+    pub fn parent(&self) -> Option<LtreePath> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(LtreePath { labels: self.labels[..self.labels.len() - 1].to_vec() })
+        }
+    }
+
+    /// Whether `self` is a prefix of `other`, a path being its own ancestor as in ltree's `@>`.
+    pub fn is_ancestor_of(&self, other: &LtreePath) -> bool {
+        other.labels.starts_with(&self.labels)
+    }
+
+    /// Labels from `start` to `end` exclusive, counted from 0.
+    /// `end` past the last label is clamped, as in Postgres' `subltree`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tree::LtreePath;
+    /// let path: LtreePath = "Top.Child1.Child2".parse().unwrap();
+    /// assert_eq!( path.subltree( 1, 2 ).unwrap().to_string(), "Child1" );
+    /// ```
+    pub fn subltree(&self, start: isize, end: isize) -> Result<LtreePath, PathError> {
+        let nlevel = self.labels.len() as isize;
+        if start < 0 || end < 0 || start >= nlevel || start > end {
+            return Err(PathError::InvalidPositions { start, end });
+        }
+        let end = end.min(nlevel);
+        Ok(LtreePath { labels: self.labels[start as usize..end as usize].to_vec() })
+    }
+
+    /// `len` labels from `offset`, as in Postgres' `subpath`. A negative `offset` counts
+    /// from the end of the path, a negative `len` leaves that many labels out at the end,
+    /// no `len` takes all the labels up to the end.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tree::LtreePath;
+    /// let path: LtreePath = "Top.Child1.Child2".parse().unwrap();
+    /// assert_eq!( path.subpath( 0, Some(2) ).unwrap().to_string(), "Top.Child1" );
+    /// assert_eq!( path.subpath( -2, None ).unwrap().to_string(), "Child1.Child2" );
+    /// ```
+    pub fn subpath(&self, offset: isize, len: Option<isize>) -> Result<LtreePath, PathError> {
+        let nlevel = self.labels.len() as isize;
+        let start = if offset < 0 { nlevel + offset } else { offset };
+        let end = match len {
+            Some(len) if len < 0 => nlevel + len,
+            Some(len) => start.saturating_add(len),
+            None => MAX_LEVELS as isize,
+        };
+        self.subltree(start, end)
+    }
+
+    /// Position of the first occurrence of `other` in `self` at or after `offset`,
+    /// as in Postgres' `index`. A negative `offset` counts from the end of the path.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tree::LtreePath;
+    /// let path: LtreePath = "0.1.2.3.5.4.5.6.8.5.6.8".parse().unwrap();
+    /// let sub: LtreePath = "5.6".parse().unwrap();
+    /// assert_eq!( path.index( &sub, 0 ), Some(6) );
+    /// assert_eq!( path.index( &sub, -4 ), Some(9) );
+    /// ```
+    pub fn index(&self, other: &LtreePath, offset: isize) -> Option<usize> {
+        let nlevel = self.labels.len();
+        let start = if offset >= 0 {
+            offset as usize
+        } else {
+            nlevel.saturating_sub(offset.unsigned_abs())
+        };
+        if other.is_empty() || start >= nlevel || nlevel - start < other.labels.len() {
+            return None;
+        }
+        self.labels[start..].windows(other.labels.len())
+            .position(|window| window == other.labels.as_slice())
+            .map(|position| start + position)
+    }
+
+    /// Longest common ancestor of `paths`, as in Postgres' `lca`: the longest common prefix,
+    /// which is a proper ancestor of every path. `None` if `paths` is or contains an empty path.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tree::LtreePath;
+    /// let paths: Vec<LtreePath> = vec![ "1.2.3".parse().unwrap(), "1.2.3.4.5.6".parse().unwrap() ];
+    /// assert_eq!( LtreePath::lca( &paths ).unwrap().to_string(), "1.2" );
+    /// ```
+    pub fn lca<'a, I: IntoIterator<Item = &'a LtreePath>>(paths: I) -> Option<LtreePath> {
+        let mut paths = paths.into_iter();
+        let first = paths.next()?;
+        let mut len = first.labels.len().checked_sub(1)?;
+        for path in paths {
+            len = len.min(path.labels.len().checked_sub(1)?);
+            len = first.labels[..len].iter().zip(&path.labels).take_while(|(a, b)| a == b).count();
+        }
+        Some(LtreePath { labels: first.labels[..len].to_vec() })
+    }
+}
+
+impl FromStr for LtreePath {
+    type Err = PathError;
+    fn from_str(s: &str) -> Result<Self, PathError> { LtreePath::parse(s) }
+}
+
+impl Display for LtreePath {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        for (i, label) in self.labels.iter().enumerate() {
+            if i > 0 {
+                write!(f, "{}", SEPARATOR)?;
+            }
+            f.write_str(label)?;
+        }
+        Ok(())
+    }
+}
+
+impl AsRef<[String]> for LtreePath {
+    fn as_ref(&self) -> &[String] { &self.labels }
+}
+
+impl<'a> IntoIterator for &'a LtreePath {
+    type Item = &'a String;
+    type IntoIter = std::slice::Iter<'a, String>;
+
+    fn into_iter(self) -> Self::IntoIter { self.labels.iter() }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let path = LtreePath::parse("Top.Science_1.Astro-nomy").unwrap();
+        assert_eq!(path.labels(), &["Top", "Science_1", "Astro-nomy"]);
+        assert_eq!(path.to_string(), "Top.Science_1.Astro-nomy");
+        assert!(LtreePath::parse("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(LtreePath::parse("Top..x"), Err(PathError::EmptyLabel { position: 4 }));
+        assert_eq!(LtreePath::parse(".x"), Err(PathError::EmptyLabel { position: 0 }));
+        assert_eq!(LtreePath::parse("Top.x y"), Err(PathError::InvalidChar { position: 5, ch: ' ' }));
+
+        let long = "a".repeat(MAX_LABEL_LEN + 1);
+        assert_eq!(LtreePath::parse(&long), Err(PathError::LabelTooLong { position: 0, len: MAX_LABEL_LEN + 1 }));
+
+        let deep = vec!["a"; MAX_LEVELS + 1].join(".");
+        assert_eq!(LtreePath::parse(&deep), Err(PathError::TooManyLevels { levels: MAX_LEVELS + 1 }));
+    }
+
+    fn path(s: &str) -> LtreePath { s.parse().unwrap() }
+
+    #[test]
+    fn test_functions() {
+        let p = path("Top.Child1.Child2");
+        assert_eq!(p.nlevel(), 3);
+        assert_eq!(p.subltree(1, 2), Ok(path("Child1")));
+        assert_eq!(p.subltree(1, 10), Ok(path("Child1.Child2")));
+        assert_eq!(p.subltree(2, 1), Err(PathError::InvalidPositions { start: 2, end: 1 }));
+        assert_eq!(p.subltree(3, 3), Err(PathError::InvalidPositions { start: 3, end: 3 }));
+        assert_eq!(p.subpath(0, Some(2)), Ok(path("Top.Child1")));
+        assert_eq!(p.subpath(1, None), Ok(path("Child1.Child2")));
+        assert_eq!(p.subpath(-1, None), Ok(path("Child2")));
+        assert_eq!(p.subpath(0, Some(-1)), Ok(path("Top.Child1")));
+        assert_eq!(p.subpath(1, Some(0)), Ok(path("")));
+        assert!(p.subpath(-4, None).is_err());
+        assert_eq!(p.subpath(1, Some(isize::MAX)), Ok(path("Child1.Child2")));
+        assert!(p.subpath(isize::MAX, Some(isize::MAX)).is_err());
+        assert!(p.subpath(isize::MIN, Some(isize::MAX)).is_err());
+        assert!(p.subpath(0, Some(isize::MIN)).is_err());
+        assert!(p.subpath(isize::MIN, None).is_err());
+
+        let p = path("0.1.2.3.5.4.5.6.8.5.6.8");
+        assert_eq!(p.index(&path("5.6"), 0), Some(6));
+        assert_eq!(p.index(&path("5.6"), -4), Some(9));
+        assert_eq!(p.index(&path("5.6"), -100), Some(6));
+        assert_eq!(p.index(&path("5.7"), 0), None);
+        assert_eq!(p.index(&path(""), 0), None);
+
+        assert_eq!(LtreePath::lca(&[path("1.2.3"), path("1.2.3.4.5.6")]), Some(path("1.2")));
+        assert_eq!(LtreePath::lca(&[path("1.2.2.3"), path("1.2.3")]), Some(path("1.2")));
+        assert_eq!(LtreePath::lca(&[path("1"), path("1.2")]), Some(path("")));
+        assert_eq!(LtreePath::lca(&[path("1.2"), path("")]), None);
+        assert_eq!(LtreePath::lca(&[]), None);
+        assert!(path("1.2").is_ancestor_of(&path("1.2.3")));
+        assert!(!path("1.2.3").is_ancestor_of(&path("1.2")));
+    }
+
+    #[test]
+    fn test_parent() {
+        let path = LtreePath::parse("Top.Science").unwrap();
+        assert_eq!(path.parent(), Some(LtreePath::parse("Top").unwrap()));
+        assert_eq!(LtreePath::new().parent(), None);
+    }
+}
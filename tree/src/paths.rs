@@ -0,0 +1,303 @@
+use super::{Node, Iter, Label, LtreePath, Walk, Traversal, Size};
+use super::bfs::Visit as BfsVisit;
+use super::walk::Visit;
+use super::rust::*;
+
+
+// Traversals telling where each node sits: its depth or its label path below the node
+// the traversal started from, the start node itself being at depth 0 with the empty path.
+// Paths are kept up to date one label at a time rather than rebuilt from the root.
+// Each of them can be bounded by `limit`, see `Traversal`.
+
+
+/// Depth-first, pre-order `(path, node)` pairs, see [`Node::dfs_paths`](struct.Node.html#method.dfs_paths).
+pub struct DfsPaths<'a, T:'a> {
+    walk    : Walk<'a,T>,
+    started : bool,
+    labels  : Vec<String>,
+    nodes   : Vec<&'a Node<T>>, // the nodes `labels` come from
+}
+
+impl<'a, T:'a> DfsPaths<'a,T> {
+    #[inline] pub fn limit( mut self, limits: Traversal<'a,T> ) -> Self { self.walk = self.walk.limit( limits ); self }
+}
+
+impl<'a, T:'a + Label> Iterator for DfsPaths<'a,T> {
+    type Item = ( LtreePath, &'a Node<T> );
+
+    fn next( &mut self ) -> Option<Self::Item> {
+        loop {
+            match self.walk.advance( &mut self.started )? {
+                Visit::Begin( node ) | Visit::Leaf( node ) => {
+                    // Usually the labels up to the parent are still there, but skipped
+                    // visits may have left those of another branch behind.
+                    let depth = self.walk.depth();
+                    self.labels.truncate( depth.saturating_sub( 1 ));
+                    self.nodes.truncate( depth.saturating_sub( 1 ));
+                    while let Some( &last ) = self.nodes.last() {
+                        if ptr::eq( last, self.walk.ancestor( self.nodes.len() )) {
+                            break;
+                        }
+                        self.nodes.pop();
+                        self.labels.pop();
+                    }
+                    while self.nodes.len() < depth {
+                        let ancestor = self.walk.ancestor( self.nodes.len() + 1 );
+                        self.nodes.push( ancestor );
+                        self.labels.push( ancestor.data.label().to_owned() );
+                    }
+                    return Some(( LtreePath::from_labels( self.labels.clone() ), node ));
+                },
+                Visit::End( _ ) => (),
+            }
+        }
+    }
+}
+
+/// Depth-first, pre-order `(depth, node)` pairs, see [`Node::dfs_depths`](struct.Node.html#method.dfs_depths).
+pub struct DfsDepths<'a, T:'a> {
+    walk    : Walk<'a,T>,
+    started : bool,
+}
+
+impl<'a, T:'a> DfsDepths<'a,T> {
+    #[inline] pub fn limit( mut self, limits: Traversal<'a,T> ) -> Self { self.walk = self.walk.limit( limits ); self }
+}
+
+impl<'a, T:'a> Iterator for DfsDepths<'a,T> {
+    type Item = ( usize, &'a Node<T> );
+
+    fn next( &mut self ) -> Option<Self::Item> {
+        loop {
+            match self.walk.advance( &mut self.started )? {
+                Visit::Begin( node ) | Visit::Leaf( node ) => return Some(( self.walk.depth(), node )),
+                Visit::End( _ ) => (),
+            }
+        }
+    }
+}
+
+/// Breadth-first `(path, node)` pairs, see [`Node::bfs_paths`](struct.Node.html#method.bfs_paths).
+///
+/// Every node whose children are still to come is queued with its own path,
+/// which its children's paths are made of.
+pub struct BfsPaths<'a, T:'a> {
+    start   : Option<&'a Node<T>>,
+    parents : VecDeque<( Vec<String>, usize, Iter<'a,T> )>,
+    limits  : Traversal<'a,T>,
+}
+
+impl<'a, T:'a> BfsPaths<'a,T> {
+    #[inline] pub fn limit( mut self, limits: Traversal<'a,T> ) -> Self { self.limits = limits; self }
+}
+
+impl<'a, T:'a + Label> Iterator for BfsPaths<'a,T> {
+    type Item = ( LtreePath, &'a Node<T> );
+
+    fn next( &mut self ) -> Option<Self::Item> {
+        if let Some( start ) = self.start.take() {
+            if !start.is_leaf() && self.limits.descends( start, 0 ) {
+                self.parents.push_back(( Vec::new(), 0, start.iter() ));
+            }
+            if self.limits.yields( 0 ) {
+                return Some(( LtreePath::new(), start ));
+            }
+        }
+        loop {
+            let ( parent, depth, children ) = self.parents.front_mut()?;
+            let depth = *depth + 1;
+            match children.next() {
+                Some( node ) => {
+                    let mut labels = Vec::with_capacity( parent.len() + 1 );
+                    labels.extend_from_slice( parent );
+                    labels.push( node.data.label().to_owned() );
+                    if !node.is_leaf() && self.limits.descends( node, depth ) {
+                        self.parents.push_back(( labels.clone(), depth, node.iter() ));
+                    }
+                    if self.limits.yields( depth ) {
+                        return Some(( LtreePath::from_labels( labels ), node ));
+                    }
+                },
+                None => { self.parents.pop_front(); },
+            }
+        }
+    }
+}
+
+/// Breadth-first `(depth, node)` pairs, see [`Node::bfs_depths`](struct.Node.html#method.bfs_depths).
+pub struct BfsDepths<'a, T:'a> {
+    start   : Option<&'a Node<T>>,
+    parents : VecDeque<( usize, Iter<'a,T> )>,
+    limits  : Traversal<'a,T>,
+}
+
+impl<'a, T:'a> BfsDepths<'a,T> {
+    #[inline] pub fn limit( mut self, limits: Traversal<'a,T> ) -> Self { self.limits = limits; self }
+}
+
+impl<'a, T:'a> BfsDepths<'a,T> {
+    /// The next `(depth, node)` pair, with whether the children of the node are visited.
+    fn next_visit( &mut self ) -> Option<( usize, &'a Node<T>, bool )> {
+        if let Some( start ) = self.start.take() {
+            let descends = !start.is_leaf() && self.limits.descends( start, 0 );
+            if descends {
+                self.parents.push_back(( 0, start.iter() ));
+            }
+            if self.limits.yields( 0 ) {
+                return Some(( 0, start, descends ));
+            }
+        }
+        loop {
+            let ( depth, children ) = self.parents.front_mut()?;
+            let depth = *depth + 1;
+            match children.next() {
+                Some( node ) => {
+                    let descends = !node.is_leaf() && self.limits.descends( node, depth );
+                    if descends {
+                        self.parents.push_back(( depth, node.iter() ));
+                    }
+                    if self.limits.yields( depth ) {
+                        return Some(( depth, node, descends ));
+                    }
+                },
+                None => { self.parents.pop_front(); },
+            }
+        }
+    }
+}
+
+impl<'a, T:'a> Iterator for BfsDepths<'a,T> {
+    type Item = ( usize, &'a Node<T> );
+
+    fn next( &mut self ) -> Option<Self::Item> {
+        self.next_visit().map( |( depth, node, _ )| ( depth, node ))
+    }
+}
+
+/// Breadth-first visits as those of [`Node::bfs`](struct.Node.html#method.bfs), bounded by a `Traversal`,
+/// see [`Node::bfs_limited`](struct.Node.html#method.bfs_limited).
+pub struct BfsLimited<'a, T:'a> {
+    depths : BfsDepths<'a,T>,
+}
+
+impl<'a, T:'a> Iterator for BfsLimited<'a,T> {
+    type Item = BfsVisit<&'a T>;
+
+    fn next( &mut self ) -> Option<Self::Item> {
+        let ( _, node, descends ) = self.depths.next_visit()?;
+        let degree = if descends { node.degree() } else { 0 };
+        Some( BfsVisit{ data: &node.data, size: Size{ degree: degree as u32, node_cnt: node.link.size.node_cnt }})
+    }
+}
+
+
+impl<T> Node<T> {
+    /// Like [`bfs`](struct.Node.html#method.bfs), bounded by `limits`: the `degree` of a visit
+    /// only counts the children which are visited, 0 below `max_depth` or where `descend` fails.
+    /// `node_cnt` is not bounded: it counts the whole subtree of the node in the tree,
+    /// pruned descendants included, not the visits which follow.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tree::{tr, Traversal};
+    /// let tree = tr(0) /( tr(1)/tr(2) ) /tr(3);
+    /// let visits = tree.root().bfs_limited( Traversal::new().max_depth(1) )
+    ///     .map( |visit| ( *visit.data, visit.size.degree )).collect::<Vec<_>>();
+    /// assert_eq!( visits, vec![ (0,2), (1,0), (3,0) ]);
+    /// ```
+    pub fn bfs_limited<'a>( &'a self, limits: Traversal<'a,T> ) -> BfsLimited<'a,T> { BfsLimited{ depths: self.bfs_depths().limit( limits )}}
+
+    /// This node and its descendants in pre-order, with their depth below this node.
+    pub fn dfs_depths( &self ) -> DfsDepths<'_,T> { DfsDepths{ walk: self.walk(), started: false }}
+
+    /// This node and its descendants in breadth-first order, with their depth below this node.
+    pub fn bfs_depths( &self ) -> BfsDepths<'_,T> { BfsDepths{ start: Some( self ), parents: VecDeque::new(), limits: Traversal::new() }}
+}
+
+impl<T:Label> Node<T> {
+    /// This node and its descendants in pre-order, with their path relative to this node.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tree::tr;
+    /// let mut tree = tr( String::new() );
+    /// tree.insert_path( &"a.b".parse().unwrap(), "b".to_owned() );
+    /// tree.insert_path( &"c".parse().unwrap(), "c".to_owned() );
+    /// let paths = tree.root().dfs_paths().map( |( path, _ )| path.to_string() ).collect::<Vec<_>>();
+    /// assert_eq!( paths, vec![ "", "a", "a.b", "c" ]);
+    /// ```
+    pub fn dfs_paths( &self ) -> DfsPaths<'_,T> { DfsPaths{ walk: self.walk(), started: false, labels: Vec::new(), nodes: Vec::new() }}
+
+    /// This node and its descendants in breadth-first order, with their path relative to this node.
+    pub fn bfs_paths( &self ) -> BfsPaths<'_,T> { BfsPaths{ start: Some( self ), parents: VecDeque::new(), limits: Traversal::new() }}
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::super::{tr, LtreePath, Node, Traversal};
+    use super::super::walk::Visit;
+    use std::cell::Cell;
+
+    #[test]
+    fn test_paths() {
+        let mut tree = tr( String::new() );
+        for path in &[ "a.b.c", "a.d", "e.f" ] {
+            let path: LtreePath = path.parse().unwrap();
+            tree.insert_path( &path, path.last().unwrap().to_owned() );
+        }
+
+        let dfs = tree.root().dfs_paths().map( |( path, node )| ( path.to_string(), node.data.clone() )).collect::<Vec<_>>();
+        let expected = [ "", "a", "a.b", "a.b.c", "a.d", "e", "e.f" ];
+        assert_eq!( dfs.iter().map( |( path, _ )| path.as_str() ).collect::<Vec<_>>(), expected );
+        assert!( dfs.iter().skip(1).all( |( path, label )| path.ends_with( label.as_str() )));
+
+        let bfs = tree.root().bfs_paths().map( |( path, _ )| path.to_string() ).collect::<Vec<_>>();
+        assert_eq!( bfs, vec![ "", "a", "e", "a.b", "a.d", "e.f", "a.b.c" ]);
+
+        let a = tree.get_path( &"a".parse().unwrap() ).unwrap();
+        assert_eq!( a.dfs_paths().map( |( path, _ )| path.to_string() ).collect::<Vec<_>>(), vec![ "", "b", "b.c", "d" ]);
+
+        let depths = tree.root().dfs_depths().map( |( depth, _ )| depth ).collect::<Vec<_>>();
+        assert_eq!( depths, vec![ 0, 1, 2, 3, 2, 1, 2 ]);
+        let depths = tree.root().bfs_depths().map( |( depth, _ )| depth ).collect::<Vec<_>>();
+        assert_eq!( depths, vec![ 0, 1, 1, 2, 2, 2, 3 ]);
+    }
+
+    #[test]
+    fn test_limits() {
+        let mut tree = tr( String::new() );
+        for path in &[ "a.b.c", "a.d", "e.f" ] {
+            let path: LtreePath = path.parse().unwrap();
+            tree.insert_path( &path, path.last().unwrap().to_owned() );
+        }
+        let depth_2 = || Traversal::new().min_depth(2).max_depth(2);
+        let paths = tree.root().dfs_paths().limit( depth_2() ).map( |( path, _ )| path.to_string() ).collect::<Vec<_>>();
+        assert_eq!( paths, vec![ "a.b", "a.d", "e.f" ]);
+        let paths = tree.root().bfs_paths().limit( depth_2() ).map( |( path, _ )| path.to_string() ).collect::<Vec<_>>();
+        assert_eq!( paths, vec![ "a.b", "a.d", "e.f" ]);
+
+        let not_b = || Traversal::new().min_depth(1).descend( |node: &Node<String>| node.data != "b" );
+        let depths = tree.root().dfs_depths().limit( not_b() ).map( |( depth, node )| ( depth, node.data.as_str() )).collect::<Vec<_>>();
+        assert_eq!( depths, vec![ (1,"a"), (2,"b"), (2,"d"), (1,"e"), (2,"f") ]);
+        let depths = tree.root().bfs_depths().limit( not_b() ).map( |( _, node )| node.data.as_str() ).collect::<Vec<_>>();
+        assert_eq!( depths, vec![ "a", "e", "b", "d", "f" ]);
+
+        let mut walk = tree.root().walk().limit( Traversal::new().max_depth(1) );
+        assert_eq!( walk.get(), Some( Visit::Begin( tree.root() )));
+        assert!( matches!( walk.next(), Some( Visit::Leaf( node )) if node.data == "a" ));
+        assert!( matches!( walk.next(), Some( Visit::Leaf( node )) if node.data == "e" ));
+        assert_eq!( walk.next(), Some( Visit::End( tree.root() )));
+        assert_eq!( walk.next(), None );
+
+        let visits = tree.root().bfs_limited( not_b() ).map( |visit| ( visit.data.as_str(), visit.size.degree, visit.size.node_cnt )).collect::<Vec<_>>();
+        assert_eq!( visits, vec![ ("a",2,4), ("e",1,2), ("b",0,2), ("d",0,1), ("f",0,1) ]);
+
+        let calls = Cell::new( 0 );
+        let counted = Traversal::new().descend( |_: &Node<String>| { calls.set( calls.get() + 1 ); true });
+        assert_eq!( tree.root().bfs_limited( counted ).count(), tree.node_count() );
+        assert_eq!( calls.get(), 4 ); // the nodes with children, once each
+    }
+}
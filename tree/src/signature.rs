@@ -0,0 +1,129 @@
+use super::{Node, Link, Label, Tree};
+use super::rust::*;
+use std::ops::{BitOr, BitOrAssign};
+
+
+/// Bloom filter of the labels of a subtree, kept in every `Link` next to `Size`.
+///
+/// A label sets one of 128 bits, so a signature lacking that bit proves the label
+/// is nowhere in the subtree and lets `Lquery::search` and `Ltxtquery::search` skip it.
+///
+/// Adding children merges their signatures into the ancestors, removing them does not
+/// clear anything: signatures only ever err on the side of labels which are gone.
+/// Nodes made by `Tree::new` get `Signature::FULL` since their data may have no label,
+/// path insertion and `Node::build_signatures` give them exact ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Signature( u128 );
+
+impl Signature {
+    pub const EMPTY : Signature = Signature( 0 );
+    pub const FULL  : Signature = Signature( !0 );
+
+    /// FNV-1a of the label, folded to a single bit.
+    pub fn of_label( label: &str ) -> Self {
+        let mut hash: u64 = 0xcbf29ce484222325;
+        for byte in label.bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul( 0x100000001b3 );
+        }
+        Signature( 1 << ( hash % 128 ))
+    }
+
+    #[inline] pub fn contains( self, other: Signature ) -> bool { self.0 & other.0 == other.0 }
+
+    /// Whether `label` may be in the subtree, `false` meaning it is certainly not.
+    #[inline] pub fn may_contain( self, label: &str ) -> bool { self.contains( Signature::of_label( label ))}
+
+    #[inline] pub fn is_full( self ) -> bool { self == Signature::FULL }
+}
+
+impl BitOr for Signature {
+    type Output = Signature;
+    #[inline] fn bitor( self, rhs: Signature ) -> Signature { Signature( self.0 | rhs.0 )}
+}
+
+impl BitOrAssign for Signature {
+    #[inline] fn bitor_assign( &mut self, rhs: Signature ) { self.0 |= rhs.0; }
+}
+
+
+impl Link {
+    /// Merges `sig` into the signatures of `self` and its ancestors,
+    /// stopping at the first one which already contains it.
+    pub(crate) fn merge_signature( &mut self, sig: Signature ) {
+        let mut link = self as *mut Self;
+        unsafe {
+            while !link.is_null() && !(*link).sig.contains( sig ) {
+                (*link).sig |= sig;
+                link = (*link).parent;
+            }
+        }
+    }
+}
+
+impl<T> Node<T> {
+    #[inline] pub fn signature( &self ) -> Signature { self.link.sig }
+}
+
+impl<T:Label> Node<T> {
+    /// Recomputes the exact signatures of this node and its descendants,
+    /// dropping the labels of removed nodes and the `FULL` signatures of `Tree::new`.
+    pub fn build_signatures( &mut self ) {
+        let mut order: Vec<*mut Node<T>> = Vec::with_capacity( self.node_count() );
+        let mut stack = vec![ self as *mut Node<T> ];
+        while let Some( node ) = stack.pop() {
+            order.push( node );
+            unsafe {
+                for child in (*node).iter_mut() {
+                    stack.push( child.get_unchecked_mut() as *mut Node<T> );
+                }
+            }
+        }
+
+        // Children come after their parent in `order`, so they are done first.
+        for &node in order.iter().rev() {
+            unsafe {
+                let mut sig = Signature::of_label( (*node).data.label() );
+                for child in (*node).iter() {
+                    sig |= child.link.sig;
+                }
+                (*node).link.sig = sig;
+            }
+        }
+    }
+}
+
+impl<T:Label> Tree<T> {
+    /// A single node tree with the exact signature of its label.
+    pub(crate) fn labeled( data: T ) -> Self {
+        let sig = Signature::of_label( data.label() );
+        let mut tree = Tree::new( data );
+        tree.link_mut().sig = sig;
+        tree
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::LtreePath;
+
+    #[test]
+    fn test_signatures() {
+        let mut tree = Tree::labeled( String::new() );
+        for path in &[ "a.b.c", "a.d", "e" ] {
+            let path: LtreePath = path.parse().unwrap();
+            tree.insert_path( &path, path.last().unwrap().to_owned() );
+        }
+        let a = tree.get_path( &"a".parse().unwrap() ).unwrap();
+        assert!( a.signature().may_contain( "c" ));
+        assert!( !a.signature().is_full() );
+        assert!( tree.root().signature().contains( a.signature() | Signature::of_label( "e" )));
+
+        tree.root_mut().get_mut().onto_iter().next().unwrap().depart();
+        assert!( tree.root().signature().may_contain( "c" ));
+        tree.root_mut().get_mut().build_signatures();
+        assert_eq!( tree.root().signature(), Signature::of_label( "" ) | Signature::of_label( "e" ));
+    }
+}
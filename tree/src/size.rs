@@ -21,7 +21,7 @@ impl AddAssign for Size {
     fn add_assign(&mut self, rhs: Self) {
         *self = Size {
             degree: self.degree + rhs.degree,
-            node_cnt: self.node_cnt + rhs.degree,
+            node_cnt: self.node_cnt + rhs.node_cnt,
         }
     }
 }
@@ -42,7 +42,7 @@ impl SubAssign for Size {
     fn sub_assign(&mut self, rhs: Self) {
         *self = Size {
             degree: self.degree - rhs.degree,
-            node_cnt: self.node_cnt - rhs.degree,
+            node_cnt: self.node_cnt - rhs.node_cnt,
         }
     }
 }
@@ -0,0 +1,64 @@
+use super::Node;
+use super::rust::*;
+
+
+type Descend<'a, T> = Box<dyn Fn( &Node<T> ) -> bool + 'a>;
+
+/// Options bounding a traversal, given to `limit` on a [`Walk`](walk/struct.Walk.html)
+/// or on the iterators of `Node::dfs_paths`, `Node::bfs_depths` and the like.
+///
+/// Depths count from the node the traversal starts at, which is at depth 0.
+/// Nodes above `min_depth` are passed through without being yielded,
+/// nodes below `max_depth` are not visited, and neither are the descendants
+/// of the nodes `descend` rejects.
+///
+/// # Examples
+///
+/// ```
+/// use tree::{tr, Traversal};
+/// let tree = tr(0) /( tr(1)/tr(2) ) /( tr(3)/tr(4) );
+/// let traversal = Traversal::new().min_depth(1).descend( |node| node.data != 3 );
+/// let visited = tree.root().bfs_depths().limit( traversal ).map( |( _, node )| node.data ).collect::<Vec<_>>();
+/// assert_eq!( visited, vec![ 1, 3, 2 ]);
+/// ```
+pub struct Traversal<'a, T> {
+    min_depth : usize,
+    max_depth : usize,
+    descend   : Option<Descend<'a, T>>,
+}
+
+impl<'a, T> Traversal<'a, T> {
+    /// No bounds, every node is visited.
+    #[inline] pub fn new() -> Self { Traversal{ min_depth: 0, max_depth: usize::MAX, descend: None }}
+
+    #[inline] pub fn min_depth( mut self, depth: usize ) -> Self { self.min_depth = depth; self }
+    #[inline] pub fn max_depth( mut self, depth: usize ) -> Self { self.max_depth = depth; self }
+
+    /// Only the children of nodes for which `descend` holds are visited.
+    #[inline] pub fn descend<F:Fn( &Node<T> ) -> bool + 'a>( mut self, descend: F ) -> Self {
+        self.descend = Some( Box::new( descend ));
+        self
+    }
+
+    /// Whether the node at `depth` is yielded.
+    #[inline] pub(crate) fn yields( &self, depth: usize ) -> bool { depth >= self.min_depth }
+
+    /// Whether the children of `node`, which is at `depth`, are visited.
+    #[inline] pub(crate) fn descends( &self, node: &Node<T>, depth: usize ) -> bool {
+        depth < self.max_depth && self.descend.as_ref().is_none_or( |descend| descend( node ))
+    }
+}
+
+impl<'a, T> Default for Traversal<'a, T> {
+    #[inline] fn default() -> Self { Traversal::new() }
+}
+
+impl<'a, T> Debug for Traversal<'a, T> {
+    fn fmt( &self, f: &mut Formatter ) -> fmt::Result {
+        f.debug_struct( "Traversal" )
+            .field( "min_depth", &self.min_depth )
+            .field( "max_depth", &self.max_depth )
+            .field( "descend", &self.descend.is_some() )
+            .finish()
+    }
+}
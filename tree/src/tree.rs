@@ -1,5 +1,6 @@
 use super::{Node, Link, Forest};
 use super::{heap, Size};
+use super::{Label, LtreePath, Entry};
 use super::bfs::{BfsTree, Splitted, Split};
 use super::forest::IntoIter;
 use super::rust::*;
@@ -37,8 +38,10 @@ impl<T> Tree<T> {
     /// assert_eq!( tree, tr(0) );
     /// ```
     #[inline] pub fn abandon( &mut self ) -> Forest<T> {
-        let forest = Forest::<T>::from( self.root().tail(), self.root().size );
+        let mut forest = Forest::<T>::from( self.root().tail(), self.root().size );
+        forest.link.sig = self.root().link.sig;
         self.link_mut().reset_child();
+        self.link_mut().index = None;
         self.link_mut().size.degree = 0;
         self.link_mut().size.node_cnt = 1;
         forest
@@ -69,6 +72,21 @@ impl<T> Tree<T> {
         BfsTree::from( self, size )
     }
 
+    /// Gets the entry of the node addressed by `path` from the root.
+    #[inline] pub fn entry( &mut self, path: &LtreePath ) -> Entry<'_,T> where T: Label {
+        self.root_mut_().entry( path )
+    }
+
+    /// Inserts `data` at `path`, creating missing intermediate nodes,
+    /// see [`Node::insert_path`](struct.Node.html#method.insert_path).
+    #[inline] pub fn insert_path( &mut self, path: &LtreePath, data: T ) -> Pin<&mut Node<T>> where T: Label {
+        self.root_mut_().insert_path( path, data )
+    }
+
+    #[inline] pub fn get_path_mut( &mut self, path: &LtreePath ) -> Option<Pin<&mut Node<T>>> where T: Label {
+        self.root_mut_().get_path_mut( path )
+    }
+
     #[inline] pub(crate) fn from( root: *mut Link ) -> Self { Tree{ root: root as *mut Node<T>, mark: PhantomData }}
     #[inline] pub(crate) fn clear( mut self ) { self.root = null_mut(); }
 }
@@ -1,4 +1,4 @@
-use super::{Tree, Forest, Node};
+use super::{Tree, Forest, Node, Traversal};
 use super::rust::*;
 
 
@@ -11,7 +11,7 @@ pub enum Visit<'a, T:'a> {
 
 
 impl<'a, T:'a> Visit<'a, T> {
-    pub fn node(&self) -> &Node<T> {
+    pub fn node(&self) -> &'a Node<T> {
         match *self {
             Visit::Begin( node ) => node,
             Visit::End  ( node ) => node,
@@ -32,7 +32,7 @@ impl<T> Nodes<T> {
     fn this(node: *const Node<T>) -> Self {
         Nodes{
             node,
-            sentinel: unsafe{(*node).next as *const Node<T>}
+            sentinel: unsafe{(*node).link.next as *const Node<T>}
         }
     }
 
@@ -51,15 +51,16 @@ enum Direction {
 }
 
 
-struct Walk<T> {
+struct RawWalk<T> {
     path: Vec<Nodes<T>>,
     direction: Direction,
     visit_type: VisitType,
     origin: *const Node<T>,
+    sibs: bool, // whether the walk covers the siblings of `origin`, i.e. a forest
 }
 
 
-impl<T> Walk<T> {
+impl<T> RawWalk<T> {
 
     #[inline] fn reset( &mut self ) {
         self.path.clear();
@@ -87,6 +88,7 @@ impl<T> Walk<T> {
         self.path.push( Nodes::this( node ));
         self.init_visit();
         self.origin = node;
+        self.sibs = false;
     }
 
     #[inline] fn on_forest( &mut self, head: *const Node<T> ) {
@@ -94,12 +96,13 @@ impl<T> Walk<T> {
         self.path.push( Nodes::sibs( head ));
         self.init_visit();
         self.origin = head;
+        self.sibs = true;
     }
 
     #[inline] fn revisit(&mut self) {
         if !self.origin.is_null() {
             match self.visit_type {
-                VisitType::None => self.path.push(Nodes::sibs(self.origin)),
+                VisitType::None => self.path.push(if self.sibs { Nodes::sibs(self.origin) } else { Nodes::this(self.origin) }),
                 _ => (),
             }
             self.direction = Direction::Down;
@@ -107,7 +110,15 @@ impl<T> Walk<T> {
         }
     }
 
-    #[inline] fn get( &self ) -> Option<Visit<T>> {
+    /// Turns the `Begin` visit of the current node into a `Leaf` one, so that its children are skipped.
+    #[inline] fn skip_children( &mut self ) {
+        if let VisitType::Begin = self.visit_type {
+            self.visit_type = VisitType::Leaf;
+            self.direction = Direction::Right;
+        }
+    }
+
+    #[inline] fn get<'a>( &self ) -> Option<Visit<'a,T>> {
         if let Some( nodes ) = self.path.last() {
             unsafe { match self.visit_type {
                 VisitType::Begin => Some( Visit::Begin( &*nodes.node )),
@@ -156,7 +167,7 @@ impl<T> Walk<T> {
 
                 Direction::Right => {
                     if let Some( nodes ) = self.path.last_mut() {
-                        nodes.node = unsafe{ (*nodes.node).next as *const Node<T> };
+                        nodes.node = unsafe{ (*nodes.node).link.next as *const Node<T> };
                         if nodes.node == nodes.sentinel {
                             self.direction = Direction::Up;
                             continue;
@@ -176,12 +187,12 @@ impl<T> Walk<T> {
 
     }
 
-    #[inline] fn next( &mut self ) -> Option<Visit<T>> {
+    #[inline] fn next<'a>( &mut self ) -> Option<Visit<'a,T>> {
         self.forward();
         self.get()
     }
 
-    #[inline] fn to_parent( &mut self ) -> Option<Visit<T>> {
+    #[inline] fn to_parent<'a>( &mut self ) -> Option<Visit<'a,T>> {
         if self.path.last().is_some() {
             self.path.pop();
             if self.path.last().is_some() {
@@ -195,7 +206,7 @@ impl<T> Walk<T> {
         None
     }
 
-    #[inline] fn get_parent( &self ) -> Option<&Node<T>> {
+    #[inline] fn get_parent<'a>( &self ) -> Option<&'a Node<T>> {
         if self.path.len() >= 2 {
             self.path.get( self.path.len()-2 ).map( |parent| unsafe{ &*parent.node })
         } else {
@@ -203,10 +214,10 @@ impl<T> Walk<T> {
         }
     }
 
-    #[inline] fn to_sib( &mut self, n: usize ) -> Option<Visit<T>> {
+    #[inline] fn to_sib<'a>( &mut self, n: usize ) -> Option<Visit<'a,T>> {
         if let Some( nodes ) = self.path.last_mut() {
             for _ in 0..n {
-                nodes.node = unsafe{ (*nodes.node).next as *const Node<T> };
+                nodes.node = unsafe{ (*nodes.node).link.next as *const Node<T> };
                 if nodes.node == nodes.sentinel {
                     self.direction = Direction::Up;
                     return None;
@@ -224,7 +235,7 @@ impl<T> Walk<T> {
         return self.get();
     }
 
-    #[inline] fn to_child( &mut self, n: usize ) -> Option<Visit<T>> {
+    #[inline] fn to_child<'a>( &mut self, n: usize ) -> Option<Visit<'a,T>> {
         let new_nodes;
         if let Some( nodes ) = self.path.last_mut() {
             let node = unsafe{ &*nodes.node };
@@ -244,15 +255,164 @@ impl<T> Walk<T> {
     }
 }
 
-impl<T> Default for Walk<T> {
+impl<T> Default for RawWalk<T> {
     #[inline] fn default() -> Self {
-        Walk{ path: Vec::default(), direction: Direction::Down, visit_type: VisitType::None, origin: null() }
+        RawWalk{ path: Vec::default(), direction: Direction::Down, visit_type: VisitType::None, origin: null(), sibs: false }
+    }
+}
+
+/// Depth-first walk borrowing a node and its descendants, or the trees of a forest.
+///
+/// Built by [`Node::walk`](../struct.Node.html#method.walk) and [`Forest::walk`](../struct.Forest.html#method.walk),
+/// the visits live as long as the borrow rather than the walk.
+///
+/// A walk bounded by `limit` visits the nodes whose children it does not descend into as `Leaf`.
+/// Its bounds apply to moving forward, a walk moved with `to_child` or `to_sib` only
+/// still skips the children of the nodes it should not descend into.
+///
+/// # Examples
+///
+/// ```
+/// use tree::{tr, walk::Visit};
+/// let tree = tr(0) /( tr(1)/tr(2) );
+/// let mut walk = tree.root().walk();
+/// assert_eq!( walk.get(), Some( Visit::Begin( tree.root() )));
+/// assert_eq!( walk.next().map( |visit| visit.node().data ), Some(1) );
+/// assert_eq!( walk.next(), Some( Visit::Leaf( tree.root().first().unwrap().first().unwrap() )));
+/// ```
+pub struct Walk<'a, T:'a> {
+    walk   : RawWalk<T>,
+    limits : Traversal<'a,T>,
+    mark   : PhantomData<&'a Node<T>>,
+}
+
+impl<'a, T:'a> Walk<'a, T> {
+    #[inline] pub(crate) fn on_node( node: &'a Node<T> ) -> Self {
+        let mut walk = RawWalk::<T>::default();
+        walk.on_node( node );
+        Walk{ walk, limits: Traversal::new(), mark: PhantomData }
+    }
+
+    #[inline] pub(crate) fn on_forest( forest: &'a Forest<T> ) -> Self {
+        let mut walk = RawWalk::<T>::default();
+        if !forest.is_empty() {
+            walk.on_forest( unsafe{ forest.head() as *const Node<T> });
+        }
+        Walk{ walk, limits: Traversal::new(), mark: PhantomData }
+    }
+
+    /// The current visit, `None` once the walk is over.
+    #[inline] pub fn get( &self ) -> Option<Visit<'a,T>> { self.walk.get() }
+
+    /// How many levels the current node is below the walked node, or the forest's trees.
+    #[inline] pub fn depth( &self ) -> usize { self.walk.path.len().saturating_sub(1) }
+
+    /// The ancestor of the current node at `depth`, the current node itself at `self.depth()`.
+    #[inline] pub(crate) fn ancestor( &self, depth: usize ) -> &'a Node<T> { unsafe{ &*self.walk.path[ depth ].node }}
+
+    /// Bounds the rest of the walk, moving on to the first visit within them if need be.
+    pub fn limit( mut self, limits: Traversal<'a,T> ) -> Self {
+        self.limits = limits;
+        self.settle();
+        self
+    }
+
+    /// Skips the children of the current node if it should not be descended into,
+    /// and the visits above the minimum depth.
+    fn settle( &mut self ) {
+        while let Some( visit ) = self.walk.get() {
+            let depth = self.depth();
+            if let Visit::Begin( node ) = visit {
+                if !self.limits.descends( node, depth ) {
+                    self.walk.skip_children();
+                }
+            }
+            if self.limits.yields( depth ) {
+                break;
+            }
+            self.walk.forward();
+        }
+    }
+
+    #[inline] fn prune( &mut self ) {
+        if let Some( Visit::Begin( node )) = self.walk.get() {
+            if !self.limits.descends( node, self.depth() ) {
+                self.walk.skip_children();
+            }
+        }
+    }
+
+    #[inline] pub fn forward( &mut self ) { self.walk.forward(); self.settle(); }
+    #[inline] pub fn next( &mut self ) -> Option<Visit<'a,T>> { self.forward(); self.get() }
+
+    /// Leaves the rest of the current node's siblings, landing on the `End` of their parent.
+    #[inline] pub fn to_parent( &mut self ) -> Option<Visit<'a,T>> { self.walk.to_parent() }
+    #[inline] pub fn get_parent( &self ) -> Option<&'a Node<T>> { self.walk.get_parent() }
+
+    /// Goes to the `n`th child of the current node, `None` if there are not that many.
+    #[inline] pub fn to_child( &mut self, n: usize ) -> Option<Visit<'a,T>> { self.walk.to_child(n)?; self.prune(); self.get() }
+    #[inline] pub fn to_sib( &mut self, n: usize ) -> Option<Visit<'a,T>> { self.walk.to_sib(n)?; self.prune(); self.get() }
+
+    /// Starts over once the walk is over, or the current node again otherwise.
+    #[inline] pub fn revisit( &mut self ) { self.walk.revisit(); self.settle(); }
+
+    /// The nodes in pre-order, i.e. on their `Begin` or `Leaf` visits.
+    #[inline] pub fn pre_order( self ) -> PreOrder<'a,T> { PreOrder{ walk: self, started: false }}
+
+    /// The nodes in post-order, i.e. on their `End` or `Leaf` visits.
+    #[inline] pub fn post_order( self ) -> PostOrder<'a,T> { PostOrder{ walk: self, started: false }}
+
+    #[inline] pub(crate) fn advance( &mut self, started: &mut bool ) -> Option<Visit<'a,T>> {
+        if *started {
+            self.next()
+        } else {
+            *started = true;
+            self.get()
+        }
+    }
+}
+
+/// Iterator over the nodes of a `Walk` in pre-order.
+pub struct PreOrder<'a, T:'a> {
+    walk    : Walk<'a,T>,
+    started : bool,
+}
+
+impl<'a, T:'a> Iterator for PreOrder<'a,T> {
+    type Item = &'a Node<T>;
+
+    fn next( &mut self ) -> Option<&'a Node<T>> {
+        loop {
+            match self.walk.advance( &mut self.started )? {
+                Visit::Begin( node ) | Visit::Leaf( node ) => return Some( node ),
+                Visit::End( _ ) => (),
+            }
+        }
+    }
+}
+
+/// Iterator over the nodes of a `Walk` in post-order.
+pub struct PostOrder<'a, T:'a> {
+    walk    : Walk<'a,T>,
+    started : bool,
+}
+
+impl<'a, T:'a> Iterator for PostOrder<'a,T> {
+    type Item = &'a Node<T>;
+
+    fn next( &mut self ) -> Option<&'a Node<T>> {
+        loop {
+            match self.walk.advance( &mut self.started )? {
+                Visit::End( node ) | Visit::Leaf( node ) => return Some( node ),
+                Visit::Begin( _ ) => (),
+            }
+        }
     }
 }
 
 pub struct TreeWalk<T> {
     tree : Tree<T>,
-    walk : Walk<T>,
+    walk : RawWalk<T>,
 }
 
 
@@ -433,7 +593,7 @@ impl<T> TreeWalk<T> {
 
 impl<T> From<Tree<T>> for TreeWalk<T> {
     fn from( tree: Tree<T> ) -> Self {
-        let mut walk = Walk::<T>::default();
+        let mut walk = RawWalk::<T>::default();
         walk.on_node( tree.root );
         TreeWalk{ tree, walk }
     }
@@ -445,7 +605,7 @@ impl<T> Into<Tree<T>> for TreeWalk<T> { fn into( self ) -> Tree<T> { self.tree }
 #[derive( Default )]
 pub struct ForestWalk<T> {
     forest : Forest<T>,
-    walk   : Walk<T>,
+    walk   : RawWalk<T>,
 }
 
 
@@ -613,7 +773,7 @@ impl<T> ForestWalk<T> {
 
 impl<T> From<Forest<T>> for ForestWalk<T> {
     fn from( forest: Forest<T> ) -> Self {
-        let mut walk = Walk::<T>::default();
+        let mut walk = RawWalk::<T>::default();
         if !forest.is_empty() {
             walk.on_forest( unsafe{ forest.head() as *const Node<T> });
         }
@@ -625,3 +785,41 @@ impl<T> Into<Forest<T>> for ForestWalk<T> { fn into( self ) -> Forest<T> { self.
 
 
 
+
+
+#[cfg(test)]
+mod tests {
+    use super::Visit;
+    use super::super::{tr, fr};
+
+    #[test]
+    fn test_orders() {
+        let tree = tr(0) /( tr(1)/tr(2)/tr(3) ) /( tr(4)/tr(5) );
+        let pre = tree.root().walk().pre_order().map( |node| node.data ).collect::<Vec<_>>();
+        assert_eq!( pre, vec![ 0, 1, 2, 3, 4, 5 ]);
+        let post = tree.root().walk().post_order().map( |node| node.data ).collect::<Vec<_>>();
+        assert_eq!( post, vec![ 2, 3, 1, 5, 4, 0 ]);
+
+        // a child is walked without its siblings
+        let first = tree.root().first().unwrap();
+        assert_eq!( first.walk().pre_order().map( |node| node.data ).collect::<Vec<_>>(), vec![ 1, 2, 3 ]);
+
+        let forest = -( tr(1)/tr(2) ) -tr(3);
+        assert_eq!( forest.walk().post_order().map( |node| node.data ).collect::<Vec<_>>(), vec![ 2, 1, 3 ]);
+        assert_eq!( fr::<i32>().walk().pre_order().count(), 0 );
+    }
+
+    #[test]
+    fn test_walk() {
+        let tree = tr(0) /( tr(1)/tr(2) ) /tr(3);
+        let mut walk = tree.root().walk();
+        assert_eq!( walk.to_child(1).map( |visit| visit.node().data ), Some(3) );
+        assert_eq!( walk.depth(), 1 );
+        assert_eq!( walk.get_parent().map( |node| node.data ), Some(0) );
+        assert_eq!( walk.to_parent(), Some( Visit::End( tree.root() )));
+        assert_eq!( walk.next(), None );
+
+        walk.revisit();
+        assert_eq!( walk.get(), Some( Visit::Begin( tree.root() )));
+    }
+}